@@ -1,58 +1,144 @@
-use crate::{runtime::RuntimeError, value::Value};
+use crate::{
+    runtime::RuntimeError,
+    value::{Type, Value},
+};
+use std::cmp::Ordering;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BiOperator {
     Add,
     Subtract,
     Multiply,
     Divide,
+    /// Division rounded towards negative infinity.
+    FloorDivide,
+    /// Floored remainder: the result takes the sign of the divisor, so that
+    /// `a == (a // b) * b + a % b`.
+    Remainder,
     Power,
+    /// Bitwise operators act on the two's complement of integers only.
+    BitAnd,
+    BitOr,
+    BitXor,
+    /// Shifts by `0..64` bits; bits shifted out are discarded.
+    ShiftLeft,
+    /// Arithmetic shift by `0..64` bits, keeping the sign.
+    ShiftRight,
     Conjuction,
     Disjunction,
     LessThan,
     LessThanOrEqual,
     GreaterThan,
     GreaterThanOrEqual,
+    Equal,
     NotEqual,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
 impl BiOperator {
+    /// Applies the operator.
+    ///
+    /// Two integers give an integer, except for `/` which always divides exactly and
+    /// gives a float; integer overflow and an integer `//` or `%` by zero are errors.
+    /// An integer mixed with a float is promoted to a float, and bitwise operators
+    /// reject floats.
     pub fn apply(&self, left: Value, right: Value) -> Result<Value, RuntimeError> {
         use BiOperator as Bi;
         use Value::*;
 
-        let result = match (left, right) {
-            (Numerical(left), Numerical(right)) => match self {
-                Bi::Add => (left + right).into(),
-                Bi::Subtract => (left - right).into(),
-                Bi::Multiply => (left * right).into(),
-                Bi::Divide => (left / right).into(),
-                Bi::Power => left.powf(right).into(),
-                Bi::LessThan => (left < right).into(),
-                Bi::LessThanOrEqual => (left <= right).into(),
-                Bi::GreaterThan => (left > right).into(),
-                Bi::GreaterThanOrEqual => (left >= right).into(),
+        match self {
+            Bi::Equal => return Ok(left.structural_eq(&right).into()),
+            Bi::NotEqual => return Ok((!left.structural_eq(&right)).into()),
+            _ => {}
+        }
+
+        let result = match (&left, &right) {
+            (Integer(left), Integer(right)) => return self.apply_integers(*left, *right),
+            (Numerical(_) | Integer(_), Numerical(_) | Integer(_)) if self.is_bitwise() => {
+                return Err(format!(
+                    "Bitwise operator `{:?}` needs integers, got {} and {}",
+                    self, left, right
+                )
+                .into())
+            }
+            (Numerical(_) | Integer(_), Numerical(_) | Integer(_)) if self.is_comparison() => {
+                // Converting an integer past 2^53 to a float would round it.
+                let ordering = left.compare_numbers(&right);
+
+                match self {
+                    Bi::LessThan => matches!(ordering, Some(Ordering::Less)),
+                    Bi::LessThanOrEqual => {
+                        matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+                    }
+                    Bi::GreaterThan => matches!(ordering, Some(Ordering::Greater)),
+                    _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                }
+                .into()
+            }
+            (Numerical(_) | Integer(_), Numerical(_) | Integer(_)) => {
+                let left = f64::try_from(&left)?;
+                let right = f64::try_from(&right)?;
+
+                match self {
+                    Bi::Add => (left + right).into(),
+                    Bi::Subtract => (left - right).into(),
+                    Bi::Multiply => (left * right).into(),
+                    Bi::Divide => (left / right).into(),
+                    Bi::FloorDivide => (left / right).floor().into(),
+                    Bi::Remainder => {
+                        // `%` is exact, unlike `left - right * (left / right).floor()`.
+                        let remainder = left % right;
+
+                        if remainder == 0.0 {
+                            0.0_f64.copysign(right).into()
+                        } else if (remainder < 0.0) != (right < 0.0) {
+                            (remainder + right).into()
+                        } else {
+                            remainder.into()
+                        }
+                    }
+                    Bi::Power => left.powf(right).into(),
+                    _ => {
+                        return Err(format!(
+                            "Invalid types for numerical binary operator `{:?}`: {:?} and {:?}",
+                            self, left, right
+                        )
+                        .into())
+                    }
+                }
+            }
+            (Boolean(left), Boolean(right)) => match self {
+                BiOperator::Conjuction => *left && *right,
+                BiOperator::Disjunction => *left || *right,
                 _ => {
                     return Err(format!(
-                        "Invalid types for numerical binary operator `{:?}`: {:?} and {:?}",
+                        "Invalid types for logical binary operator `{:?}`: {:?} and {:?}",
                         self, left, right
                     )
                     .into())
                 }
-            },
-            (Boolean(left), Boolean(right)) => match self {
-                BiOperator::Conjuction => left && right,
-                BiOperator::Disjunction => left || right,
+            }
+            .into(),
+            (Str(left), Str(right)) => match self {
+                Bi::Add => Value::from(format!("{}{}", left, right)),
+                Bi::LessThan => (left < right).into(),
+                Bi::LessThanOrEqual => (left <= right).into(),
+                Bi::GreaterThan => (left > right).into(),
+                Bi::GreaterThanOrEqual => (left >= right).into(),
                 _ => {
                     return Err(format!(
-                        "Invalid types for logical binary operator `{:?}`: {:?} and {:?}",
+                        "Invalid types for string binary operator `{:?}`: {:?} and {:?}",
                         self, left, right
                     )
                     .into())
                 }
-            }
-            .into(),
+            },
 
             _ => {
                 return Err(format!(
@@ -66,6 +152,114 @@ impl BiOperator {
         Ok(result)
     }
 
+    fn apply_integers(&self, left: i64, right: i64) -> Result<Value, RuntimeError> {
+        use BiOperator as Bi;
+
+        let result = match self {
+            Bi::Add => left.checked_add(right),
+            Bi::Subtract => left.checked_sub(right),
+            Bi::Multiply => left.checked_mul(right),
+            Bi::Divide => return Ok((left as f64 / right as f64).into()),
+            Bi::FloorDivide if right == 0 => {
+                return Err(format!("Integer division by zero: {} // {}", left, right).into())
+            }
+            Bi::FloorDivide => left.checked_div(right).map(|quotient| {
+                // Division truncates towards zero; step down when it rounded up.
+                if left % right != 0 && (left < 0) != (right < 0) {
+                    quotient - 1
+                } else {
+                    quotient
+                }
+            }),
+            Bi::Remainder if right == 0 => {
+                return Err(format!("Integer remainder by zero: {} % {}", left, right).into())
+            }
+            Bi::Remainder => {
+                // Only `i64::MIN % -1` wraps, and its remainder is 0 all the same.
+                let remainder = left.wrapping_rem(right);
+
+                if remainder != 0 && (remainder < 0) != (right < 0) {
+                    Some(remainder + right)
+                } else {
+                    Some(remainder)
+                }
+            }
+            Bi::Power if right < 0 => {
+                return Ok((left as f64).powf(right as f64).into());
+            }
+            Bi::Power => u32::try_from(right)
+                .ok()
+                .and_then(|right| left.checked_pow(right)),
+            Bi::BitAnd => Some(left & right),
+            Bi::BitOr => Some(left | right),
+            Bi::BitXor => Some(left ^ right),
+            Bi::ShiftLeft | Bi::ShiftRight if !(0..64).contains(&right) => {
+                return Err(format!("Shift amount {} is out of range 0..64", right).into())
+            }
+            Bi::ShiftLeft => Some(left << right),
+            Bi::ShiftRight => Some(left >> right),
+            Bi::LessThan => return Ok((left < right).into()),
+            Bi::LessThanOrEqual => return Ok((left <= right).into()),
+            Bi::GreaterThan => return Ok((left > right).into()),
+            Bi::GreaterThanOrEqual => return Ok((left >= right).into()),
+            _ => {
+                return Err(format!(
+                    "Invalid types for numerical binary operator `{:?}`: {:?} and {:?}",
+                    self, left, right
+                )
+                .into())
+            }
+        };
+
+        result.map(Value::Integer).ok_or_else(|| {
+            format!("Integer overflow in `{:?}`: {} and {}", self, left, right).into()
+        })
+    }
+
+    /// Operand and result types accepted by [`BiOperator::apply`], as `(left, right, result)`.
+    fn signatures(&self) -> &'static [(Type, Type, Type)] {
+        use BiOperator as Bi;
+        use Type::*;
+
+        match self {
+            Bi::Add => &[(Number, Number, Number), (Str, Str, Str)],
+            Bi::Subtract
+            | Bi::Multiply
+            | Bi::Divide
+            | Bi::FloorDivide
+            | Bi::Remainder
+            | Bi::Power
+            | Bi::BitAnd
+            | Bi::BitOr
+            | Bi::BitXor
+            | Bi::ShiftLeft
+            | Bi::ShiftRight => &[(Number, Number, Number)],
+            Bi::LessThan | Bi::LessThanOrEqual | Bi::GreaterThan | Bi::GreaterThanOrEqual => {
+                &[(Number, Number, Boolean), (Str, Str, Boolean)]
+            }
+            Bi::Conjuction | Bi::Disjunction => &[(Boolean, Boolean, Boolean)],
+            Bi::Equal | Bi::NotEqual => &[(Any, Any, Boolean)],
+        }
+    }
+
+    /// Static counterpart of [`BiOperator::apply`]: the result type for the given
+    /// operand types, or `None` if no value of those types can be combined.
+    pub fn result_type(&self, left: Type, right: Type) -> Option<Type> {
+        let mut matching = self
+            .signatures()
+            .iter()
+            .filter(|(l, r, _)| l.accepts(left) && r.accepts(right))
+            .map(|(_, _, result)| *result);
+
+        let first = matching.next()?;
+
+        if matching.all(|result| result == first) {
+            Some(first)
+        } else {
+            Some(Type::Any)
+        }
+    }
+
     pub fn precedence(&self) -> u8 {
         use BiOperator as Bi;
 
@@ -74,10 +268,49 @@ impl BiOperator {
             Bi::Conjuction => 2,
             Bi::LessThan | BiOperator::LessThanOrEqual => 3,
             Bi::GreaterThan | BiOperator::GreaterThanOrEqual => 3,
-            Bi::NotEqual => 3,
-            Bi::Add | BiOperator::Subtract => 4,
-            Bi::Multiply | BiOperator::Divide => 5,
-            Bi::Power => 6,
+            Bi::Equal | BiOperator::NotEqual => 3,
+            Bi::BitOr => 4,
+            Bi::BitXor => 5,
+            Bi::BitAnd => 6,
+            Bi::ShiftLeft | Bi::ShiftRight => 7,
+            Bi::Add | BiOperator::Subtract => 8,
+            Bi::Multiply | Bi::Divide | Bi::FloorDivide | Bi::Remainder => 9,
+            Bi::Power => 10,
+        }
+    }
+
+    /// The left operand that decides the result on its own, in which case the right
+    /// operand is not evaluated: `false` for `&&` and `true` for `||`.
+    pub fn short_circuit(&self) -> Option<bool> {
+        match self {
+            BiOperator::Conjuction => Some(false),
+            BiOperator::Disjunction => Some(true),
+            _ => None,
+        }
+    }
+
+    fn is_comparison(&self) -> bool {
+        use BiOperator as Bi;
+
+        matches!(
+            self,
+            Bi::LessThan | Bi::LessThanOrEqual | Bi::GreaterThan | Bi::GreaterThanOrEqual
+        )
+    }
+
+    fn is_bitwise(&self) -> bool {
+        use BiOperator as Bi;
+
+        matches!(
+            self,
+            Bi::BitAnd | Bi::BitOr | Bi::BitXor | Bi::ShiftLeft | Bi::ShiftRight
+        )
+    }
+
+    pub fn associativity(&self) -> Associativity {
+        match self {
+            BiOperator::Power => Associativity::Right,
+            _ => Associativity::Left,
         }
     }
 }
@@ -93,6 +326,13 @@ impl FromStr for BiOperator {
             "-" => Bi::Subtract,
             "*" => Bi::Multiply,
             "/" => Bi::Divide,
+            "//" => Bi::FloorDivide,
+            "%" => Bi::Remainder,
+            "&" => Bi::BitAnd,
+            "|" => Bi::BitOr,
+            "xor" => Bi::BitXor,
+            "<<" => Bi::ShiftLeft,
+            ">>" => Bi::ShiftRight,
             "^" => Bi::Power,
             "&&" => Bi::Conjuction,
             "||" => Bi::Disjunction,
@@ -100,6 +340,7 @@ impl FromStr for BiOperator {
             "<=" => Bi::LessThanOrEqual,
             ">" => Bi::GreaterThan,
             ">=" => Bi::GreaterThanOrEqual,
+            "==" => Bi::Equal,
             "!=" => Bi::NotEqual,
             _ => return Err(format!("Unknown BiOperator: {}", value)),
         };
@@ -0,0 +1,550 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::bi_operator::BiOperator;
+use crate::expression::{Expression, ExpressionKind};
+use crate::span::SourceSpan;
+use crate::statement::{Statement, StatementKind, UserFunction};
+use crate::unary_operator::UnaryOperator;
+use crate::value::Value;
+
+/// A single VM instruction.
+///
+/// Operands index into the pools of the chunk being executed, the local slots of the
+/// current call frame or the global slots of the program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// Pushes `constants[index]`.
+    Constant(u32),
+    GetLocal(u32),
+    SetLocal(u32),
+    GetGlobal(u32),
+    /// Binds a global declared with a top-level `let`.
+    DefineGlobal(u32),
+    SetGlobal(u32),
+    /// Looks `names[index]` up in the execution context, for variables that cannot be
+    /// resolved at compile time such as the ones set by the embedder.
+    GetName(u32),
+    SetName(u32),
+    Unary(UnaryOperator),
+    Binary(BiOperator),
+    /// Pops the topmost `count` values into a new array.
+    Array(u32),
+    /// Pops the topmost `count` key and value pairs into a new map.
+    Map(u32),
+    /// Pops an index and a collection and pushes the element.
+    GetIndex,
+    /// Pops a value, an index and a collection and stores the value as the element.
+    SetIndex,
+    Jump(u32),
+    /// Pops a condition and jumps if it is `false`.
+    JumpIfFalse(u32),
+    /// Jumps to `exit` if the left operand of `&&` or `||` on top of the stack is the
+    /// boolean `result`, keeping it as the result of the whole expression.
+    ShortCircuit {
+        result: bool,
+        exit: u32,
+    },
+    /// Pops the end and start of a `for` range and stores the state returned by
+    /// `range_bounds` in the local slots `slot` and `slot + 1`.
+    ForRange(u32),
+    /// Pops an array or map and stores the position `0` in the local slot `slot` and
+    /// the values to iterate over in `slot + 1`.
+    ForEach(u32),
+    /// Pushes the next value of the range or collection in `slot` and advances it, or
+    /// jumps to `exit` once it is exhausted.
+    ForNext {
+        slot: u32,
+        exit: u32,
+    },
+    /// Calls `names[name]` with the topmost `arguments` values on the stack.
+    Call {
+        name: u32,
+        arguments: u32,
+    },
+    /// Makes `functions[index]` of the program callable by its name.
+    DeclareFunction(u32),
+    Pop,
+    Return,
+}
+
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub(crate) code: Vec<Instruction>,
+    /// Span of the expression or statement each instruction was compiled from, `None`
+    /// for the implicit return ending the chunk.
+    pub(crate) spans: Vec<Option<SourceSpan>>,
+    pub(crate) constants: Vec<Value>,
+    pub(crate) names: Vec<Rc<str>>,
+    /// Local slots a frame running this chunk needs, parameters included.
+    pub(crate) slots: usize,
+}
+
+#[derive(Debug)]
+pub struct FunctionChunk {
+    pub(crate) name: Rc<str>,
+    pub(crate) arity: usize,
+    pub(crate) chunk: Chunk,
+}
+
+/// A program lowered to bytecode for the stack-based VM.
+#[derive(Debug)]
+pub struct BytecodeProgram {
+    pub(crate) main: Rc<FunctionChunk>,
+    pub(crate) functions: Vec<Rc<FunctionChunk>>,
+    /// Names of the global slots, i.e. of the variables declared at the top level.
+    pub(crate) globals: Vec<Rc<str>>,
+}
+
+impl BytecodeProgram {
+    pub(crate) fn compile(statements: &[Statement]) -> Self {
+        let mut globals = Globals::default();
+
+        for statement in statements {
+            if let StatementKind::Declaration(name, _) = &statement.kind {
+                globals.slot(name);
+            }
+        }
+
+        let mut functions = Vec::new();
+
+        let mut compiler = ChunkCompiler::new(&globals, &mut functions, true);
+        compiler.compile_statements(statements);
+        let main = compiler.finish("<main>".into(), 0);
+
+        BytecodeProgram {
+            main: Rc::new(main),
+            functions,
+            globals: globals.names,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Globals {
+    slots: HashMap<String, u32>,
+    names: Vec<Rc<str>>,
+}
+
+impl Globals {
+    fn slot(&mut self, name: &str) -> u32 {
+        if let Some(slot) = self.slots.get(name) {
+            return *slot;
+        }
+
+        let slot = self.names.len() as u32;
+        self.slots.insert(name.to_string(), slot);
+        self.names.push(name.into());
+
+        slot
+    }
+}
+
+enum Variable {
+    Local(u32),
+    Global(u32),
+    Name(u32),
+}
+
+struct Scope {
+    first_slot: u32,
+    variables: Vec<(String, u32)>,
+}
+
+struct Loop {
+    /// Where `continue` jumps to.
+    start: u32,
+    /// `break` jumps to be patched once the end of the loop is known.
+    breaks: Vec<usize>,
+}
+
+/// Lowers the body of one function, or of the main program, into a [`Chunk`].
+///
+/// Variables are resolved lexically the same way the tree-walker scopes them: block
+/// locals get frame slots that are reused once the block ends, the top-level scope of
+/// the main program lives in global slots, and anything else is looked up by name.
+struct ChunkCompiler<'a> {
+    globals: &'a Globals,
+    functions: &'a mut Vec<Rc<FunctionChunk>>,
+    chunk: Chunk,
+    scopes: Vec<Scope>,
+    loops: Vec<Loop>,
+    next_slot: u32,
+    is_main: bool,
+    /// Span recorded for the instructions emitted next.
+    span: Option<SourceSpan>,
+}
+
+impl<'a> ChunkCompiler<'a> {
+    fn new(globals: &'a Globals, functions: &'a mut Vec<Rc<FunctionChunk>>, is_main: bool) -> Self {
+        Self {
+            globals,
+            functions,
+            chunk: Chunk::default(),
+            scopes: Vec::new(),
+            loops: Vec::new(),
+            next_slot: 0,
+            is_main,
+            span: None,
+        }
+    }
+
+    fn finish(mut self, name: Rc<str>, arity: usize) -> FunctionChunk {
+        self.span = None;
+        self.emit_constant(Value::Void);
+        self.emit(Instruction::Return);
+
+        FunctionChunk {
+            name,
+            arity,
+            chunk: self.chunk,
+        }
+    }
+
+    fn compile_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.compile_statement(statement);
+        }
+    }
+
+    fn compile_statement(&mut self, statement: &Statement) {
+        self.span = Some(statement.span);
+
+        match &statement.kind {
+            StatementKind::FunctionCall(call) => {
+                self.compile_expression(call);
+                self.emit(Instruction::Pop);
+            }
+            StatementKind::Declaration(name, expression) => {
+                self.compile_expression(expression);
+
+                if self.is_main && self.scopes.is_empty() {
+                    let slot = self.globals.slots[name];
+                    self.emit(Instruction::DefineGlobal(slot));
+                } else {
+                    let slot = self.declare_local(name);
+                    self.emit(Instruction::SetLocal(slot));
+                }
+            }
+            StatementKind::Assignment(name, expression) => {
+                self.compile_expression(expression);
+
+                let instruction = match self.resolve(name) {
+                    Variable::Local(slot) => Instruction::SetLocal(slot),
+                    Variable::Global(slot) => Instruction::SetGlobal(slot),
+                    Variable::Name(index) => Instruction::SetName(index),
+                };
+                self.emit(instruction);
+            }
+            StatementKind::IndexAssignment(array, index, value) => {
+                self.compile_expression(array);
+                self.compile_expression(index);
+                self.compile_expression(value);
+                self.emit(Instruction::SetIndex);
+            }
+            StatementKind::Conditional(condition, body, else_body) => {
+                let skip_body = self.compile_condition(condition);
+
+                self.compile_statement(body);
+
+                if let Some(else_body) = else_body {
+                    let skip_else = self.emit(Instruction::Jump(0));
+                    self.patch_jump(skip_body);
+
+                    self.compile_statement(else_body);
+                    self.patch_jump(skip_else);
+                } else {
+                    self.patch_jump(skip_body);
+                }
+            }
+            StatementKind::Loop(condition, body) => {
+                let start = self.chunk.code.len() as u32;
+
+                let exit = self.compile_condition(condition);
+
+                self.compile_loop_body(start, body);
+                self.emit(Instruction::Jump(start));
+                self.patch_jump(exit);
+                self.end_loop();
+            }
+            StatementKind::For(name, start, end, body) => {
+                self.compile_for(name, body, |compiler, slot| {
+                    compiler.compile_expression(start);
+                    compiler.compile_expression(end);
+                    compiler.emit(Instruction::ForRange(slot));
+                });
+            }
+            StatementKind::ForEach(name, collection, body) => {
+                self.compile_for(name, body, |compiler, slot| {
+                    compiler.compile_expression(collection);
+                    compiler.emit(Instruction::ForEach(slot));
+                });
+            }
+            StatementKind::Break => {
+                let jump = self.emit(Instruction::Jump(0));
+                self.loops.last_mut().unwrap().breaks.push(jump);
+            }
+            StatementKind::Continue => {
+                let start = self.loops.last().unwrap().start;
+                self.emit(Instruction::Jump(start));
+            }
+            StatementKind::CodeBlock(statements) => {
+                self.begin_scope();
+                self.compile_statements(statements);
+                self.end_scope();
+            }
+            StatementKind::FunctionDeclaration(function) => {
+                let index = self.compile_function(function);
+                self.emit(Instruction::DeclareFunction(index));
+            }
+            StatementKind::Return(expression) => {
+                match expression {
+                    Some(expression) => self.compile_expression(expression),
+                    None => self.emit_constant(Value::Void),
+                }
+
+                self.emit(Instruction::Return);
+            }
+        }
+    }
+
+    /// Compiles a loop body whose `continue` jumps to `start`; the loop is closed by
+    /// [`ChunkCompiler::end_loop`] once its exit is emitted.
+    fn compile_loop_body(&mut self, start: u32, body: &Statement) {
+        self.loops.push(Loop {
+            start,
+            breaks: Vec::new(),
+        });
+        self.compile_statement(body);
+    }
+
+    /// Compiles a `for` loop binding `name`; `start` emits the code initialising the
+    /// two hidden slots read by [`Instruction::ForNext`], starting at the given one.
+    fn compile_for(&mut self, name: &str, body: &Statement, start: impl FnOnce(&mut Self, u32)) {
+        self.begin_scope();
+
+        // Hidden slots named so that no variable can refer to them.
+        let state = self.declare_local("for next");
+        self.declare_local("for end");
+
+        start(self, state);
+
+        let next = self.emit(Instruction::ForNext {
+            slot: state,
+            exit: 0,
+        });
+
+        self.begin_scope();
+        let variable = self.declare_local(name);
+        self.emit(Instruction::SetLocal(variable));
+        self.compile_loop_body(next as u32, body);
+        self.end_scope();
+
+        self.emit(Instruction::Jump(next as u32));
+        self.patch_jump(next);
+        self.end_loop();
+        self.end_scope();
+    }
+
+    /// Points the `break` jumps of the innermost loop to the next instruction.
+    fn end_loop(&mut self) {
+        let control = self.loops.pop().unwrap();
+
+        for jump in control.breaks {
+            self.patch_jump(jump);
+        }
+    }
+
+    fn compile_function(&mut self, function: &UserFunction) -> u32 {
+        let mut compiler = ChunkCompiler::new(self.globals, self.functions, false);
+
+        compiler.begin_scope();
+        for parameter in &function.parameters {
+            compiler.declare_local(parameter);
+        }
+        compiler.compile_statements(&function.body);
+
+        let chunk = compiler.finish(function.name.as_str().into(), function.parameters.len());
+
+        self.functions.push(Rc::new(chunk));
+        (self.functions.len() - 1) as u32
+    }
+
+    /// Emits the condition and a `JumpIfFalse` to be patched, located at the condition.
+    fn compile_condition(&mut self, condition: &Expression) -> usize {
+        self.compile_expression(condition);
+
+        let outer = self.span.replace(condition.span);
+        let jump = self.emit(Instruction::JumpIfFalse(0));
+        self.span = outer;
+
+        jump
+    }
+
+    fn compile_expression(&mut self, expression: &Expression) {
+        let outer = self.span.replace(expression.span);
+        self.compile_expression_kind(expression);
+        self.span = outer;
+    }
+
+    fn compile_expression_kind(&mut self, expression: &Expression) {
+        match &expression.kind {
+            ExpressionKind::Number(number) => self.emit_constant(Value::Numerical(*number)),
+            ExpressionKind::Integer(integer) => self.emit_constant(Value::Integer(*integer)),
+            ExpressionKind::Boolean(boolean) => self.emit_constant(Value::Boolean(*boolean)),
+            ExpressionKind::Str(string) => self.emit_constant(Value::Str(string.clone())),
+            ExpressionKind::BracketExpression(inner) => self.compile_expression(inner),
+            ExpressionKind::Array(elements) => {
+                for element in elements {
+                    self.compile_expression(element);
+                }
+
+                self.emit(Instruction::Array(elements.len() as u32));
+            }
+            ExpressionKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.emit_constant(Value::Str(key.as_str().into()));
+                    self.compile_expression(value);
+                }
+
+                self.emit(Instruction::Map(entries.len() as u32));
+            }
+            ExpressionKind::Index(array, index) => {
+                self.compile_expression(array);
+                self.compile_expression(index);
+                self.emit(Instruction::GetIndex);
+            }
+            ExpressionKind::Unary(operator, operand) => {
+                self.compile_expression(operand);
+                self.emit(Instruction::Unary(*operator));
+            }
+            ExpressionKind::BinaryExpression(left, operator, right) => {
+                self.compile_expression(left);
+
+                let short_circuit = operator
+                    .short_circuit()
+                    .map(|result| self.emit(Instruction::ShortCircuit { result, exit: 0 }));
+
+                self.compile_expression(right);
+                self.emit(Instruction::Binary(*operator));
+
+                if let Some(short_circuit) = short_circuit {
+                    self.patch_jump(short_circuit);
+                }
+            }
+            ExpressionKind::Function(name, arguments) => {
+                for argument in arguments {
+                    self.compile_expression(argument);
+                }
+
+                let name = self.name(name);
+                self.emit(Instruction::Call {
+                    name,
+                    arguments: arguments.len() as u32,
+                });
+            }
+            ExpressionKind::Variable(name) => {
+                let instruction = match self.resolve(name) {
+                    Variable::Local(slot) => Instruction::GetLocal(slot),
+                    Variable::Global(slot) => Instruction::GetGlobal(slot),
+                    Variable::Name(index) => Instruction::GetName(index),
+                };
+                self.emit(instruction);
+            }
+        }
+    }
+
+    fn resolve(&mut self, name: &str) -> Variable {
+        let local = self.scopes.iter().rev().find_map(|scope| {
+            scope
+                .variables
+                .iter()
+                .rev()
+                .find(|(variable, _)| variable == name)
+                .map(|(_, slot)| *slot)
+        });
+
+        if let Some(slot) = local {
+            Variable::Local(slot)
+        } else if let Some(slot) = self.globals.slots.get(name) {
+            Variable::Global(*slot)
+        } else {
+            Variable::Name(self.name(name))
+        }
+    }
+
+    fn declare_local(&mut self, name: &str) -> u32 {
+        let scope = self.scopes.last_mut().unwrap();
+
+        // Redeclaring in the same scope rebinds the existing slot.
+        if let Some((_, slot)) = scope
+            .variables
+            .iter()
+            .find(|(variable, _)| variable == name)
+        {
+            return *slot;
+        }
+
+        let slot = self.next_slot;
+        scope.variables.push((name.to_string(), slot));
+
+        self.next_slot += 1;
+        self.chunk.slots = self.chunk.slots.max(self.next_slot as usize);
+
+        slot
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(Scope {
+            first_slot: self.next_slot,
+            variables: Vec::new(),
+        });
+    }
+
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        self.next_slot = scope.first_slot;
+    }
+
+    fn name(&mut self, name: &str) -> u32 {
+        let names = &mut self.chunk.names;
+
+        match names.iter().position(|existing| &**existing == name) {
+            Some(index) => index as u32,
+            None => {
+                names.push(name.into());
+                (names.len() - 1) as u32
+            }
+        }
+    }
+
+    fn emit_constant(&mut self, value: Value) {
+        self.chunk.constants.push(value);
+
+        let index = (self.chunk.constants.len() - 1) as u32;
+        self.emit(Instruction::Constant(index));
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.chunk.code.push(instruction);
+        self.chunk.spans.push(self.span);
+        self.chunk.code.len() - 1
+    }
+
+    /// Points the jump at `index` to the next instruction to be emitted.
+    fn patch_jump(&mut self, index: usize) {
+        let target = self.chunk.code.len() as u32;
+
+        match &mut self.chunk.code[index] {
+            Instruction::Jump(destination)
+            | Instruction::JumpIfFalse(destination)
+            | Instruction::ShortCircuit {
+                exit: destination, ..
+            }
+            | Instruction::ForNext {
+                exit: destination, ..
+            } => *destination = target,
+            _ => unreachable!("patching a non-jump instruction"),
+        }
+    }
+}
@@ -1,285 +1,959 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::iter::Peekable;
+use std::rc::Rc;
 use std::str::FromStr;
 
 use anyhow::Result;
-use pest::error::LineColLocation;
-use pest::iterators::Pair;
-use pest::{Parser, Position};
+use pest::error::{ErrorVariant, InputLocation};
+use pest::iterators::{Pair, Pairs};
+use pest::Parser;
 use pest_derive::Parser;
 use thiserror::Error;
 
-use crate::bi_operator::BiOperator;
-use crate::expression::Expression;
+use crate::bi_operator::{Associativity, BiOperator};
+use crate::bytecode::BytecodeProgram;
+use crate::diagnostic::{token_at, Diagnostic};
+use crate::expression::{Expression, ExpressionKind};
 use crate::runtime::{ExecutionContext, RuntimeError};
-use crate::statement::Statement;
+use crate::span::{LineIndex, SourceSpan, Span};
+use crate::statement::{Flow, Statement, StatementKind, UserFunction};
+use crate::type_checker::TypeChecker;
+use crate::unary_operator::UnaryOperator;
+use crate::value::Value;
 
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub struct MeadorParser;
 
-pub struct MeadorCompiler;
+pub struct MeadorCompiler<'a> {
+    index: LineIndex<'a>,
+    /// Errors of statements skipped so far; compilation goes on past them.
+    errors: RefCell<Vec<CompilationError>>,
+    /// Loops enclosing the statement being compiled, within the current function.
+    loop_depth: Cell<usize>,
+}
 
 #[derive(Debug, Error)]
 pub enum CompilationError {
-    #[error("Invalid statement at position {pos}: {context}")]
+    #[error("Invalid statement at {pos}: {context}")]
     Statement { pos: ErrorLocation, context: String },
-    #[error("Invalid expression at position {pos}: {context}")]
+    #[error("Invalid expression at {pos}: {context}")]
     Expression { pos: ErrorLocation, context: String },
-    #[error("Invalid operator at position {pos}: {context}")]
+    #[error("Invalid operator at {pos}: {context}")]
     Operator { pos: ErrorLocation, context: String },
-    #[error("Invalid value at position {pos} {context}")]
+    #[error("Invalid value at {pos}: {context}")]
     Value { pos: ErrorLocation, context: String },
-    #[error("Invalid start of program at position {pos}: {context}")]
+    #[error("Invalid start of program at {pos}: {context}")]
     StartOfProgram { pos: ErrorLocation, context: String },
+    #[error("Type error at {pos}: {context}")]
+    Type { pos: ErrorLocation, context: String },
+    /// A parse error, keeping what the grammar expected at the failing position.
+    #[error("Syntax error at {pos}: {}", describe_syntax(.expected, .unexpected, .found))]
+    Syntax {
+        pos: ErrorLocation,
+        expected: Vec<String>,
+        unexpected: Vec<String>,
+        found: String,
+    },
+    #[error("{}", display_all(.0))]
+    Multiple(Vec<CompilationError>),
 }
 
-#[derive(Debug)]
-pub enum ErrorLocation {
-    LineCol { line: usize, column: usize },
-    Position(usize),
+fn display_all(errors: &[CompilationError]) -> String {
+    errors
+        .iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-impl From<LineColLocation> for ErrorLocation {
-    fn from(line_col: LineColLocation) -> Self {
-        match line_col {
-            LineColLocation::Pos((line, column)) => Self::LineCol { line, column },
-            LineColLocation::Span((line, column), _) => Self::LineCol { line, column },
-        }
+fn describe_syntax(expected: &[String], unexpected: &[String], found: &str) -> String {
+    match (expected.is_empty(), unexpected.is_empty()) {
+        (false, true) => format!("expected {}, found {}", one_of(expected), found),
+        (true, false) => format!("unexpected {}", one_of(unexpected)),
+        (false, false) => format!(
+            "expected {}, found {} (unexpected {})",
+            one_of(expected),
+            found,
+            one_of(unexpected)
+        ),
+        (true, true) => format!("unexpected {}", found),
     }
 }
 
-impl<'a> From<Position<'a>> for ErrorLocation {
-    fn from(position: Position) -> Self {
-        Self::Position(position.pos())
+/// Joins alternatives as `a, b or c`.
+fn one_of(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [init @ .., last] => format!("{} or {}", init.join(", "), last),
     }
 }
 
-impl std::fmt::Display for ErrorLocation {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl CompilationError {
+    /// Where the error occurred, `None` for [`CompilationError::Multiple`].
+    pub fn location(&self) -> Option<ErrorLocation> {
         match self {
-            Self::LineCol { line, column } => write!(f, "line {}, column {}", line, column),
-            Self::Position(pos) => write!(f, "position {}", pos),
+            CompilationError::Statement { pos, .. }
+            | CompilationError::Expression { pos, .. }
+            | CompilationError::Operator { pos, .. }
+            | CompilationError::Value { pos, .. }
+            | CompilationError::StartOfProgram { pos, .. }
+            | CompilationError::Type { pos, .. }
+            | CompilationError::Syntax { pos, .. } => Some(*pos),
+            CompilationError::Multiple(_) => None,
+        }
+    }
+
+    /// Flattens the error into the individual errors it reports.
+    pub fn into_errors(self) -> Vec<CompilationError> {
+        match self {
+            CompilationError::Multiple(errors) => errors
+                .into_iter()
+                .flat_map(CompilationError::into_errors)
+                .collect(),
+            error => vec![error],
+        }
+    }
+
+    /// Converts the error, or each of several errors, into renderable diagnostics.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let (title, pos, context) = match self {
+            CompilationError::Multiple(errors) => {
+                return errors.iter().flat_map(Self::diagnostics).collect();
+            }
+            CompilationError::Syntax {
+                pos,
+                expected,
+                unexpected,
+                found,
+            } => {
+                let mut diagnostic =
+                    Diagnostic::error(describe_syntax(expected, unexpected, found))
+                        .with_expected(expected.clone())
+                        .with_found(found.clone());
+
+                diagnostic = diagnostic.with_span(pos.span);
+
+                if !expected.is_empty() {
+                    diagnostic = diagnostic.with_label(format!("expected {}", one_of(expected)));
+                }
+
+                if !unexpected.is_empty() {
+                    diagnostic = diagnostic.with_note(format!("unexpected {}", one_of(unexpected)));
+                }
+
+                if found == END_OF_INPUT {
+                    diagnostic = diagnostic
+                        .with_help("the program ended early, check for a missing `;` or `}`");
+                }
+
+                return vec![diagnostic];
+            }
+            CompilationError::Statement { pos, context } => ("Invalid statement", pos, context),
+            CompilationError::Expression { pos, context } => ("Invalid expression", pos, context),
+            CompilationError::Operator { pos, context } => ("Invalid operator", pos, context),
+            CompilationError::Value { pos, context } => ("Invalid value", pos, context),
+            CompilationError::StartOfProgram { pos, context } => {
+                ("Invalid start of program", pos, context)
+            }
+            CompilationError::Type { pos, context } => ("Type error", pos, context),
+        };
+
+        vec![Diagnostic::error(format!("{}: {}", title, context)).with_span(pos.span)]
+    }
+}
+
+impl From<Vec<CompilationError>> for CompilationError {
+    fn from(mut errors: Vec<CompilationError>) -> Self {
+        if errors.len() == 1 {
+            errors.remove(0)
+        } else {
+            CompilationError::Multiple(errors)
         }
     }
 }
 
-impl MeadorCompiler {
-    pub fn compile(code: &str) -> Result<Program, CompilationError> {
-        let parsed_statements = MeadorParser::parse(Rule::program, code)
-            .map_err(|err| CompilationError::StartOfProgram {
-                pos: err.line_col.into(),
-                context: "Failed to parse program".to_string(),
-            })?
+/// Where in the source an error occurred, with lines and columns.
+pub type ErrorLocation = SourceSpan;
+
+const END_OF_INPUT: &str = "end of input";
+
+/// Human-readable names of grammar rules, deduplicated in order.
+fn describe_rules(rules: &[Rule]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+
+    for rule in rules {
+        let name = match rule {
+            Rule::ident => "identifier".to_string(),
+            Rule::expr => "expression".to_string(),
+            Rule::int | Rule::decimal => "number".to_string(),
+            Rule::operand => "value".to_string(),
+            Rule::bi_operator | Rule::xor => "operator".to_string(),
+            Rule::code_block => "block".to_string(),
+            Rule::string_char | Rule::string_content => "string character".to_string(),
+            Rule::EOI => END_OF_INPUT.to_string(),
+            Rule::invalid_statement | Rule::skipped_block | Rule::unmatched_brace => continue,
+            rule => format!("{:?}", rule).replace('_', " "),
+        };
+
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+impl<'a> MeadorCompiler<'a> {
+    pub fn compile(code: &'a str) -> Result<Program, CompilationError> {
+        Self::compile_rule(Rule::program, code, HashSet::new())
+    }
+
+    /// Compiles a program to run in `context`.
+    ///
+    /// Calls to the user and host functions already defined there are not checked
+    /// against builtins of the same name, which those functions replace.
+    pub fn compile_for(
+        code: &'a str,
+        context: &ExecutionContext,
+    ) -> Result<Program, CompilationError> {
+        Self::compile_rule(Rule::program, code, context.function_names())
+    }
+
+    /// Compiles one input of an interactive session running in `context`.
+    ///
+    /// Besides statements the input may end with a bare expression, optionally followed
+    /// by `;`, whose value [`Program::evaluate`] returns. As with [`Self::compile_for`],
+    /// the functions defined by earlier inputs or the embedder may replace builtins.
+    pub fn compile_interactive(
+        code: &'a str,
+        context: &ExecutionContext,
+    ) -> Result<Program, CompilationError> {
+        Self::compile_rule(Rule::repl_input, code, context.function_names())
+    }
+
+    /// Compiles every statement it can, collecting the errors of the others.
+    ///
+    /// The grammar skips a statement that fails to parse up to the next `;` or block
+    /// boundary; the skipped text is parsed again on its own to report what was expected
+    /// there. All syntax, compilation and type errors are returned together, ordered by
+    /// position.
+    fn compile_rule(
+        rule: Rule,
+        code: &'a str,
+        defined_functions: HashSet<String>,
+    ) -> Result<Program, CompilationError> {
+        let compiler = MeadorCompiler {
+            index: LineIndex::new(code),
+            errors: RefCell::new(Vec::new()),
+            loop_depth: Cell::new(0),
+        };
+
+        let parsed_statements = MeadorParser::parse(rule, code)
+            .map_err(|err| compiler.syntax_error(err, 0))?
             .next()
             .ok_or(CompilationError::StartOfProgram {
-                pos: ErrorLocation::LineCol { line: 0, column: 0 },
+                pos: compiler.index.resolve(Span::new(0, 0)),
                 context: "No statements found in program".to_string(),
             })?
             .into_inner();
 
         let mut statements = Vec::new();
+        let mut result = None;
 
         for statement in parsed_statements {
             match statement.as_rule() {
                 Rule::EOI => break,
-                Rule::statement => {
-                    let statement = statement.into_inner().next().unwrap();
-                    let statement = Self::compile_statement(statement)?;
-                    statements.push(statement)
-                }
-                invalid_rule => {
-                    return Err(CompilationError::Statement {
-                        pos: ErrorLocation::Position(statement.as_span().start_pos().pos()),
-                        context: format!("Unexpected input: {:?}", invalid_rule),
-                    })
+                Rule::expr => match compiler.compile_expression(statement) {
+                    Ok(expression) => result = Some(expression),
+                    Err(error) => compiler.errors.borrow_mut().push(error),
+                },
+                _ => statements.extend(compiler.compile_statements(Pairs::single(statement))),
+            }
+        }
+
+        let mut errors = compiler.errors.into_inner();
+
+        if let Err(type_errors) =
+            TypeChecker::check(&statements, result.as_ref(), defined_functions)
+        {
+            errors.extend(type_errors);
+        }
+
+        if errors.is_empty() {
+            return Ok(Program { statements, result });
+        }
+
+        let mut errors: Vec<CompilationError> = errors
+            .into_iter()
+            .flat_map(CompilationError::into_errors)
+            .collect();
+        errors.sort_by_key(|error| error.location().map(|pos| pos.span.start));
+
+        Err(errors.into())
+    }
+
+    /// Compiles a sequence of statement pairs, recording the errors of those that fail,
+    /// including the ones the grammar skipped, and leaving them out.
+    fn compile_statements(&self, pairs: Pairs<Rule>) -> Vec<Statement> {
+        let mut statements = Vec::new();
+
+        for pair in pairs {
+            let compiled = match pair.as_rule() {
+                Rule::statement => self.compile_statement(pair.into_inner().next().unwrap()),
+                Rule::invalid_statement | Rule::unmatched_brace => {
+                    Err(self.invalid_statement(&pair))
                 }
+                invalid_rule => Err(CompilationError::Statement {
+                    pos: self.location(&pair),
+                    context: format!("Unexpected input: {:?}", invalid_rule),
+                }),
+            };
+
+            match compiled {
+                Ok(statement) => statements.push(statement),
+                Err(error) => self.errors.borrow_mut().push(error),
             }
         }
 
-        Ok(Program { statements })
+        statements
+    }
+
+    /// Parses the text of a skipped statement on its own to find out why it failed.
+    fn invalid_statement(&self, pair: &Pair<Rule>) -> CompilationError {
+        let offset = pair.as_span().start();
+
+        match MeadorParser::parse(Rule::single_statement, pair.as_str()) {
+            Err(error) => self.syntax_error(error, offset),
+            Ok(_) => CompilationError::Statement {
+                pos: self.location(pair),
+                context: "Statement cannot be parsed here".to_string(),
+            },
+        }
     }
 
-    fn compile_statement(statement: Pair<Rule>) -> Result<Statement, CompilationError> {
-        let statement = match statement.as_rule() {
+    /// Converts a pest error for input starting at `offset` of the compiled source.
+    fn syntax_error(&self, error: pest::error::Error<Rule>, offset: usize) -> CompilationError {
+        let pos = offset
+            + match error.location {
+                InputLocation::Pos(pos) => pos,
+                InputLocation::Span((start, _)) => start,
+            };
+
+        let (expected, unexpected) = match error.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => (describe_rules(&positives), describe_rules(&negatives)),
+            ErrorVariant::CustomError { message } => (Vec::new(), vec![message]),
+        };
+
+        let found = match token_at(self.index.source(), pos) {
+            "" => END_OF_INPUT.to_string(),
+            token => format!("`{}`", token),
+        };
+
+        CompilationError::Syntax {
+            pos: self.index.resolve(Span::new(pos, pos)),
+            expected,
+            unexpected,
+            found,
+        }
+    }
+
+    fn location(&self, pair: &Pair<Rule>) -> SourceSpan {
+        self.index.resolve(pair.as_span().into())
+    }
+
+    fn compile_statement(&self, statement: Pair<Rule>) -> Result<Statement, CompilationError> {
+        let mut span = self.location(&statement);
+        let mut doc = None;
+
+        let kind = match statement.as_rule() {
             Rule::function_call_stmt => {
                 let expression = statement.into_inner().next().unwrap();
-                Statement::FunctionCall(Self::compile_function_call(expression)?)
+                StatementKind::FunctionCall(self.compile_function_call(expression)?)
             }
             Rule::variable_declaration => {
+                let mut inner = statement.into_inner().peekable();
+                doc = self.doc_comment(&mut inner, &mut span);
+                let name = inner.next().unwrap().as_str().to_string();
+
+                let expression = inner.next().unwrap();
+                let expression = self.compile_expression(expression)?;
+
+                StatementKind::Declaration(name, expression)
+            }
+            Rule::assignment => {
                 let mut inner = statement.into_inner();
                 let name = inner.next().unwrap().as_str().to_string();
 
                 let expression = inner.next().unwrap();
-                let expression = Self::compile_expression(expression)?;
+                let expression = self.compile_expression(expression)?;
+
+                StatementKind::Assignment(name, expression)
+            }
+            Rule::index_assignment => {
+                let mut inner: Vec<Pair<Rule>> = statement.into_inner().collect();
+
+                let value = self.compile_expression(inner.pop().unwrap())?;
+                let index = self.compile_index(inner.pop().unwrap())?;
+
+                let mut inner = inner.into_iter();
+                let name = inner.next().unwrap();
+                let variable = Expression::new(
+                    ExpressionKind::Variable(name.as_str().to_string()),
+                    self.location(&name),
+                );
+                let array = self.compile_indices(variable, inner)?;
 
-                Statement::Assignment(name, expression)
+                StatementKind::IndexAssignment(array, index, value)
             }
             Rule::if_stmt => {
                 let mut inner = statement.into_inner();
 
                 let condition = inner.next().unwrap();
-                let condition = Self::compile_expression(condition)?;
+                let condition = self.compile_expression(condition)?;
 
                 let body = inner.next().unwrap().into_inner().next().unwrap();
-                let body = Self::compile_statement(body)?;
+                let body = self.compile_body(body)?;
 
                 let else_body = inner.next().map(|statement| {
                     let else_statement = statement.into_inner().next().unwrap();
-                    Self::compile_statement(else_statement)
+                    self.compile_body(else_statement)
                 });
 
                 if let Some(else_body) = else_body {
-                    Statement::Conditional(condition, Box::new(body), Some(Box::new(else_body?)))
+                    StatementKind::Conditional(
+                        condition,
+                        Box::new(body),
+                        Some(Box::new(else_body?)),
+                    )
                 } else {
-                    Statement::Conditional(condition, Box::new(body), None)
+                    StatementKind::Conditional(condition, Box::new(body), None)
                 }
             }
             Rule::while_loop => {
                 let mut inner = statement.into_inner();
 
                 let condition = inner.next().unwrap();
-                let condition = Self::compile_expression(condition)?;
+                let condition = self.compile_expression(condition)?;
 
                 let body = inner.next().unwrap().into_inner().next().unwrap();
-                let body = Self::compile_statement(body)?;
+                let body = self.compile_loop_body(body)?;
 
-                Statement::Loop(condition, Box::new(body))
+                StatementKind::Loop(condition, Box::new(body))
             }
-            Rule::code_block => {
-                let statements: Result<Vec<Statement>, CompilationError> = statement
-                    .into_inner()
-                    .map(|stmt| stmt.into_inner().next().unwrap())
-                    .map(|stmt| Self::compile_statement(stmt))
-                    .collect();
+            Rule::for_loop => {
+                let mut inner = statement.into_inner();
+                let name = inner.next().unwrap().as_str().to_string();
+
+                let mut parts: Vec<Pair<Rule>> = inner.collect();
+
+                let body = parts.pop().unwrap().into_inner().next().unwrap();
+                let body = Box::new(self.compile_loop_body(body)?);
 
-                Statement::CodeBlock(statements?)
+                let mut bounds = parts.into_iter();
+                let start = self.compile_expression(bounds.next().unwrap())?;
+
+                match bounds.next() {
+                    Some(end) => {
+                        StatementKind::For(name, start, self.compile_expression(end)?, body)
+                    }
+                    None => StatementKind::ForEach(name, start, body),
+                }
+            }
+            Rule::break_stmt | Rule::continue_stmt => {
+                let (kind, keyword) = match statement.as_rule() {
+                    Rule::break_stmt => (StatementKind::Break, "break"),
+                    _ => (StatementKind::Continue, "continue"),
+                };
+
+                if self.loop_depth.get() == 0 {
+                    return Err(CompilationError::Statement {
+                        pos: span,
+                        context: format!("`{}` outside of a loop", keyword),
+                    });
+                }
+
+                kind
+            }
+            Rule::function_declaration => {
+                let mut inner = statement.into_inner().peekable();
+                doc = self.doc_comment(&mut inner, &mut span);
+                let name = inner.next().unwrap().as_str().to_string();
+
+                let mut parameters = Vec::new();
+                let mut body = inner.next().unwrap();
+
+                if body.as_rule() == Rule::parameters {
+                    parameters = body
+                        .into_inner()
+                        .map(|param| param.as_str().to_string())
+                        .collect();
+                    body = inner.next().unwrap();
+                }
+
+                // Loops around the declaration cannot be left from within the function.
+                let loop_depth = self.loop_depth.replace(0);
+                let body = self.compile_statements(body.into_inner());
+                self.loop_depth.set(loop_depth);
+
+                StatementKind::FunctionDeclaration(Rc::new(UserFunction {
+                    name,
+                    parameters,
+                    body,
+                }))
+            }
+            Rule::return_stmt => {
+                let expression = statement.into_inner().next();
+                let expression = expression
+                    .map(|expression| self.compile_expression(expression))
+                    .transpose()?;
+
+                StatementKind::Return(expression)
+            }
+            Rule::code_block => {
+                StatementKind::CodeBlock(self.compile_statements(statement.into_inner()))
             }
             invalid_rule => {
                 return Err(CompilationError::Statement {
-                    pos: ErrorLocation::Position(statement.as_span().start_pos().pos()),
+                    pos: span,
                     context: format!("Unexpected input: {:?}", invalid_rule),
                 })
             }
         };
 
-        Ok(statement)
+        Ok(Statement { kind, span, doc })
     }
 
-    fn compile_expression(pair: Pair<Rule>) -> Result<Expression, CompilationError> {
-        let mut inner = pair.into_inner();
+    /// Consumes the `///` comments leading a declaration and joins their lines. The
+    /// span of a documented declaration is narrowed to start after its comments.
+    fn doc_comment(
+        &self,
+        inner: &mut Peekable<Pairs<Rule>>,
+        span: &mut SourceSpan,
+    ) -> Option<String> {
+        let mut lines = Vec::new();
+        let mut end = 0;
+
+        while let Some(comment) = inner.next_if(|pair| pair.as_rule() == Rule::doc_comment) {
+            end = comment.as_span().end();
+            let line = &comment.as_str()["///".len()..];
+            lines.push(line.strip_prefix(' ').unwrap_or(line).trim_end());
+        }
+
+        if lines.is_empty() {
+            return None;
+        }
 
-        let left = inner.next().unwrap().into_inner().next().unwrap();
-        let left = Self::compile_value_expression(left)?;
+        let after_comments = &self.index.source()[end..span.span.end];
+        let start = span.span.end - after_comments.trim_start().len();
+        *span = self.index.resolve(Span::new(start, span.span.end));
 
-        if let Some(operator) = Self::parse_operator(inner.next()) {
-            Self::compile_binary_expression(left, operator, inner)
-        } else {
-            Ok(left)
+        Some(lines.join("\n"))
+    }
+
+    /// Compiles the body of an `if`, `else` or `while`, giving it its own scope
+    /// even when it is a single statement rather than a block.
+    fn compile_body(&self, statement: Pair<Rule>) -> Result<Statement, CompilationError> {
+        let statement = self.compile_statement(statement)?;
+
+        match statement.kind {
+            StatementKind::CodeBlock(_) => Ok(statement),
+            _ => {
+                let span = statement.span;
+                Ok(Statement::new(
+                    StatementKind::CodeBlock(vec![statement]),
+                    span,
+                ))
+            }
         }
     }
 
+    /// Compiles the body of a loop, where `break` and `continue` are allowed.
+    fn compile_loop_body(&self, statement: Pair<Rule>) -> Result<Statement, CompilationError> {
+        self.loop_depth.set(self.loop_depth.get() + 1);
+        let body = self.compile_body(statement);
+        self.loop_depth.set(self.loop_depth.get() - 1);
+
+        body
+    }
+
+    fn compile_expression(&self, pair: Pair<Rule>) -> Result<Expression, CompilationError> {
+        let mut inner = pair.into_inner().peekable();
+
+        self.compile_binary_expression(&mut inner, 0)
+    }
+
+    /// Precedence climbing over the flat `operand (bi_operator operand)*` sequence of an
+    /// `expr`.
     fn compile_binary_expression(
-        left: Expression,
-        operator: BiOperator,
-        mut inner: pest::iterators::Pairs<Rule>,
+        &self,
+        inner: &mut Peekable<Pairs<Rule>>,
+        min_precedence: u8,
     ) -> Result<Expression, CompilationError> {
-        let right = inner.next().unwrap().into_inner().next().unwrap();
-        let right = Self::compile_value_expression(right)?;
-
-        if let Some(next_operator) = Self::parse_operator(inner.next()) {
-            if next_operator.precedence() > operator.precedence() {
-                let right = Self::compile_binary_expression(right, next_operator, inner)?;
-                Ok(Expression::BinaryExpression(
-                    Box::new(left),
-                    operator,
-                    Box::new(right),
-                ))
-            } else {
-                let lh = Expression::BinaryExpression(Box::new(left), operator, Box::new(right));
-                Self::compile_binary_expression(lh, next_operator, inner)
+        let left = self.compile_operand(inner)?;
+
+        self.compile_operators(left, inner, min_precedence)
+    }
+
+    /// Compiles the next operand with its prefix operators.
+    ///
+    /// Prefix operators bind tighter than every binary operator but `^`, so `-2 ^ 2` is
+    /// `-(2 ^ 2)` while `2 ^ -1` still raises to a negative power.
+    fn compile_operand(
+        &self,
+        inner: &mut Peekable<Pairs<Rule>>,
+    ) -> Result<Expression, CompilationError> {
+        let mut parts = inner.next().unwrap().into_inner();
+        let mut operators = Vec::new();
+
+        let value = loop {
+            let pair = parts.next().unwrap();
+
+            match pair.as_rule() {
+                Rule::unary_operator => operators.push(pair),
+                _ => break pair.into_inner().next().unwrap(),
             }
-        } else {
-            Ok(Expression::BinaryExpression(
-                Box::new(left),
-                operator,
-                Box::new(right),
-            ))
+        };
+
+        let value = self.compile_value_expression(value)?;
+        let value = self.compile_indices(value, parts)?;
+
+        if operators.is_empty() {
+            return Ok(value);
         }
+
+        let mut operand = self.compile_operators(value, inner, BiOperator::Power.precedence())?;
+
+        for pair in operators.iter().rev() {
+            let operator = UnaryOperator::from_str(pair.as_str()).map_err(|err| {
+                CompilationError::Operator {
+                    pos: self.location(pair),
+                    context: err,
+                }
+            })?;
+            let span = self.location(pair).to(operand.span);
+
+            operand = Expression::new(ExpressionKind::Unary(operator, Box::new(operand)), span);
+        }
+
+        Ok(operand)
     }
 
-    fn parse_operator(pair: Option<Pair<Rule>>) -> Option<BiOperator> {
-        pair.and_then(|pair| match pair.as_rule() {
-            Rule::bi_operator => {
-                let operator = pair.as_str();
-                Some(BiOperator::from_str(operator).unwrap())
-            }
-            _ => None,
-        })
+    /// Applies the `[index]` and `.field` suffixes following a value, from left to
+    /// right; `value.field` is the same as `value["field"]`.
+    fn compile_indices<'i>(
+        &self,
+        mut array: Expression,
+        indices: impl Iterator<Item = Pair<'i, Rule>>,
+    ) -> Result<Expression, CompilationError> {
+        for index in indices {
+            let span = array.span.to(self.location(&index));
+            let index = self.compile_index(index)?;
+
+            array = Expression::new(
+                ExpressionKind::Index(Box::new(array), Box::new(index)),
+                span,
+            );
+        }
+
+        Ok(array)
     }
 
-    fn compile_value_expression(value: Pair<Rule>) -> Result<Expression, CompilationError> {
-        let value = match value.as_rule() {
-            Rule::int | Rule::decimal => {
-                let number = value.as_str().trim().parse::<f64>().map_err(|_| {
-                    CompilationError::Value {
-                        pos: ErrorLocation::Position(value.as_span().start_pos().pos()),
-                        context: "Failed to parse number".to_string(),
-                    }
-                })?;
-                Expression::Number(number)
+    /// Compiles the key of an `[index]` or `.field` suffix.
+    fn compile_index(&self, index: Pair<Rule>) -> Result<Expression, CompilationError> {
+        let inner = index.into_inner().next().unwrap();
+
+        match inner.as_rule() {
+            Rule::ident => Ok(Expression::new(
+                ExpressionKind::Str(inner.as_str().into()),
+                self.location(&inner),
+            )),
+            _ => self.compile_expression(inner),
+        }
+    }
+
+    /// Consumes operators binding at least as tight as `min_precedence` to the right of
+    /// `left`; left-associative operators raise the bound for their right operand,
+    /// right-associative ones keep it.
+    fn compile_operators(
+        &self,
+        mut left: Expression,
+        inner: &mut Peekable<Pairs<Rule>>,
+        min_precedence: u8,
+    ) -> Result<Expression, CompilationError> {
+        while let Some(pair) = inner.peek() {
+            let operator = self.parse_operator(pair)?;
+
+            if operator.precedence() < min_precedence {
+                break;
             }
-            Rule::boolean => {
-                let boolean = value.as_str().trim().parse::<bool>().map_err(|_| {
-                    CompilationError::Value {
-                        pos: ErrorLocation::Position(value.as_span().start_pos().pos()),
-                        context: "Failed to parse boolean".to_string(),
-                    }
-                })?;
 
-                Expression::Boolean(boolean)
+            inner.next();
+
+            let next_precedence = match operator.associativity() {
+                Associativity::Left => operator.precedence() + 1,
+                Associativity::Right => operator.precedence(),
+            };
+
+            let right = self.compile_binary_expression(inner, next_precedence)?;
+            let span = left.span.to(right.span);
+            left = Expression::new(
+                ExpressionKind::BinaryExpression(Box::new(left), operator, Box::new(right)),
+                span,
+            );
+        }
+
+        Ok(left)
+    }
+
+    fn parse_operator(&self, pair: &Pair<Rule>) -> Result<BiOperator, CompilationError> {
+        match pair.as_rule() {
+            Rule::bi_operator => {
+                BiOperator::from_str(pair.as_str()).map_err(|err| CompilationError::Operator {
+                    pos: self.location(pair),
+                    context: err,
+                })
             }
+            invalid_rule => Err(CompilationError::Operator {
+                pos: self.location(pair),
+                context: format!("Unexpected input: {:?}", invalid_rule),
+            }),
+        }
+    }
+
+    fn compile_value_expression(&self, value: Pair<Rule>) -> Result<Expression, CompilationError> {
+        let span = self.location(&value);
+
+        let kind = match value.as_rule() {
+            Rule::int => ExpressionKind::Integer(self.parse_literal(&value, "integer")?),
+            Rule::decimal => ExpressionKind::Number(self.parse_literal(&value, "number")?),
+            Rule::boolean => ExpressionKind::Boolean(self.parse_literal(&value, "boolean")?),
             Rule::parenthesis => {
                 let expression = value.into_inner().next().unwrap();
-                let expression = Self::compile_expression(expression)?;
+                let expression = self.compile_expression(expression)?;
+
+                ExpressionKind::BracketExpression(Box::new(expression))
+            }
+            Rule::array => {
+                let elements: Result<Vec<Expression>, CompilationError> = value
+                    .into_inner()
+                    .map(|element| self.compile_expression(element))
+                    .collect();
+
+                ExpressionKind::Array(elements?)
+            }
+            Rule::map => {
+                let entries: Result<Vec<(String, Expression)>, CompilationError> = value
+                    .into_inner()
+                    .map(|entry| {
+                        let mut inner = entry.into_inner();
+                        let key = inner.next().unwrap();
+                        let key = match key.as_rule() {
+                            Rule::string => self.unescape(&key.into_inner().next().unwrap())?,
+                            _ => key.as_str().to_string(),
+                        };
+
+                        Ok((key, self.compile_expression(inner.next().unwrap())?))
+                    })
+                    .collect();
+
+                ExpressionKind::Map(entries?)
+            }
+            Rule::string => {
+                let content = value.into_inner().next().unwrap();
+                let string = self.unescape(&content)?;
 
-                Expression::BracketExpression(Box::new(expression))
+                ExpressionKind::Str(string.into())
             }
-            Rule::function_call => Self::compile_function_call(value)?,
+            Rule::function_call => return self.compile_function_call(value),
             Rule::ident => {
                 let name = value.as_str().to_string();
-                Expression::Variable(name)
+                ExpressionKind::Variable(name)
             }
             _ => {
                 return Err(CompilationError::Value {
-                    pos: ErrorLocation::Position(value.as_span().start_pos().pos()),
+                    pos: self.location(&value),
                     context: format!("Unexpected input: {:?}", value),
                 })
             }
         };
 
-        Ok(value)
+        Ok(Expression::new(kind, span))
     }
 
-    fn compile_function_call(pair: Pair<Rule>) -> Result<Expression, CompilationError> {
+    fn parse_literal<T: FromStr>(
+        &self,
+        pair: &Pair<Rule>,
+        kind: &str,
+    ) -> Result<T, CompilationError> {
+        pair.as_str()
+            .trim()
+            .parse::<T>()
+            .map_err(|_| CompilationError::Value {
+                pos: self.location(pair),
+                context: format!("Failed to parse {}", kind),
+            })
+    }
+
+    /// Resolves the escape sequences accepted by the `string_char` rule.
+    fn unescape(&self, content: &Pair<Rule>) -> Result<String, CompilationError> {
+        let raw = content.as_str();
+        let mut result = String::with_capacity(raw.len());
+        let mut chars = raw.chars();
+
+        while let Some(char) = chars.next() {
+            if char != '\\' {
+                result.push(char);
+                continue;
+            }
+
+            let escaped = match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some('u') => {
+                    let hex: String = chars
+                        .by_ref()
+                        .skip(1)
+                        .take_while(|char| *char != '}')
+                        .collect();
+
+                    u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| CompilationError::Value {
+                            pos: self.location(content),
+                            context: format!("Invalid unicode escape: \\u{{{}}}", hex),
+                        })?
+                }
+                Some(other) => other,
+                None => break,
+            };
+
+            result.push(escaped);
+        }
+
+        Ok(result)
+    }
+
+    fn compile_function_call(&self, pair: Pair<Rule>) -> Result<Expression, CompilationError> {
+        let span = self.location(&pair);
         let mut inner = pair.into_inner();
         let name = inner.next().unwrap().as_str().to_string();
         let arguments: Result<Vec<Expression>, CompilationError> = inner
-            .map(|expression| Self::compile_expression(expression))
+            .map(|expression| self.compile_expression(expression))
             .collect();
 
-        Ok(Expression::Function(name, arguments?))
+        Ok(Expression::new(
+            ExpressionKind::Function(name, arguments?),
+            span,
+        ))
     }
 }
 
+/// A `let` or `fn` declaration, with its doc comment, for documentation generators
+/// and editor tooling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    /// Text of the `///` comments above the declaration, one line per comment.
+    pub doc: Option<String>,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclarationKind {
+    Variable,
+    Function,
+}
+
 pub struct Program {
     statements: Vec<Statement>,
+    /// Trailing expression of an interactive input.
+    result: Option<Expression>,
 }
 
 impl Program {
+    /// Runs the program with the tree-walking interpreter, the reference engine.
     pub fn execute(&self, context: &mut ExecutionContext) -> Result<(), RuntimeError> {
+        // A top-level `return` simply ends the program early.
+        Statement::execute_all(&self.statements, context)?;
+
+        Ok(())
+    }
+
+    /// Runs the program like [`Program::execute`] and returns the value of its trailing
+    /// expression, or of a top-level `return`, or `Void` if it has neither.
+    pub fn evaluate(&self, context: &mut ExecutionContext) -> Result<Value, RuntimeError> {
+        if let Flow::Return(value) = Statement::execute_all(&self.statements, context)? {
+            return Ok(value);
+        }
+
+        match &self.result {
+            Some(expression) => expression.evaluate(context),
+            None => Ok(Value::Void),
+        }
+    }
+
+    /// Lowers the program to bytecode for the stack-based VM.
+    pub fn to_bytecode(&self) -> BytecodeProgram {
+        BytecodeProgram::compile(&self.statements)
+    }
+
+    /// Every declaration of the program in source order, including the ones nested in
+    /// blocks and function bodies.
+    pub fn declarations(&self) -> Vec<Declaration> {
+        let mut declarations = Vec::new();
+
         for statement in &self.statements {
-            statement.execute(context)?;
+            collect_declarations(statement, &mut declarations);
         }
 
-        Ok(())
+        declarations
+    }
+}
+
+fn collect_declarations(statement: &Statement, declarations: &mut Vec<Declaration>) {
+    let declaration = |name: &str, kind| Declaration {
+        name: name.to_string(),
+        kind,
+        doc: statement.doc.clone(),
+        span: statement.span,
+    };
+
+    match &statement.kind {
+        StatementKind::Declaration(name, _) => {
+            declarations.push(declaration(name, DeclarationKind::Variable));
+        }
+        StatementKind::FunctionDeclaration(function) => {
+            declarations.push(declaration(&function.name, DeclarationKind::Function));
+
+            for statement in &function.body {
+                collect_declarations(statement, declarations);
+            }
+        }
+        StatementKind::CodeBlock(statements) => {
+            for statement in statements {
+                collect_declarations(statement, declarations);
+            }
+        }
+        StatementKind::Conditional(_, body, else_body) => {
+            collect_declarations(body, declarations);
+
+            if let Some(else_body) = else_body {
+                collect_declarations(else_body, declarations);
+            }
+        }
+        StatementKind::Loop(_, body)
+        | StatementKind::For(_, _, _, body)
+        | StatementKind::ForEach(_, _, body) => collect_declarations(body, declarations),
+        _ => {}
     }
 }
@@ -0,0 +1,237 @@
+use std::fmt::Write;
+
+use crate::span::{LineCol, LineIndex, SourceSpan, Span};
+
+/// A user-facing report of a problem in a script, renderable against its source.
+///
+/// A zero-width span marks a position rather than a range; the renderer then
+/// underlines the token starting there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Option<Span>,
+    /// Short text printed next to the underline.
+    pub label: Option<String>,
+    pub expected: Vec<String>,
+    pub found: Option<String>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            span: None,
+            label: None,
+            expected: Vec::new(),
+            found: None,
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_expected(mut self, expected: Vec<String>) -> Self {
+        self.expected = expected;
+        self
+    }
+
+    pub fn with_found(mut self, found: impl Into<String>) -> Self {
+        self.found = Some(found.into());
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Renders the diagnostic for a terminal, quoting the offending line of `source`
+    /// and underlining the span.
+    ///
+    /// ```text
+    /// error: expected expression, found `;`
+    ///  --> 1:9
+    ///   |
+    /// 1 | let x = ;
+    ///   |         ^ expected expression
+    ///   |
+    ///   = found: `;`
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let mut output = format!("error: {}\n", self.message);
+
+        if let Some(span) = self.span {
+            let index = LineIndex::new(source);
+            let span = index.resolve(widen(source, span));
+            let LineCol { line, column } = span.start;
+            let text = index.line(line);
+
+            let gutter = " ".repeat(line.to_string().len());
+            let underline = if span.end.line == line {
+                span.end.column - column
+            } else {
+                text.chars().count() + 1 - column
+            };
+            let padding = " ".repeat(column - 1);
+
+            let _ = writeln!(output, "{}--> {}:{}", gutter, line, column);
+            let _ = writeln!(output, "{} |", gutter);
+            let _ = writeln!(output, "{} | {}", line, text);
+            let _ = write!(
+                output,
+                "{} | {}{}",
+                gutter,
+                padding,
+                "^".repeat(underline.max(1))
+            );
+
+            match &self.label {
+                Some(label) => {
+                    let _ = writeln!(output, " {}", label);
+                }
+                None => output.push('\n'),
+            }
+
+            if self.has_footer() {
+                let _ = writeln!(output, "{} |", gutter);
+            }
+
+            self.render_footer(&mut output, &gutter);
+        } else {
+            self.render_footer(&mut output, "");
+        }
+
+        output
+    }
+
+    fn has_footer(&self) -> bool {
+        !self.expected.is_empty()
+            || self.found.is_some()
+            || !self.notes.is_empty()
+            || self.help.is_some()
+    }
+
+    fn render_footer(&self, output: &mut String, gutter: &str) {
+        if !self.expected.is_empty() {
+            let _ = writeln!(
+                output,
+                "{} = expected: {}",
+                gutter,
+                self.expected.join(", ")
+            );
+        }
+
+        if let Some(found) = &self.found {
+            let _ = writeln!(output, "{} = found: {}", gutter, found);
+        }
+
+        for note in &self.notes {
+            let _ = writeln!(output, "{} = note: {}", gutter, note);
+        }
+
+        if let Some(help) = &self.help {
+            let _ = writeln!(output, "{} = help: {}", gutter, help);
+        }
+    }
+
+    /// Serializes the diagnostic as a single-line JSON object, resolving the span
+    /// against `source` into 1-based lines and columns.
+    pub fn to_json(&self, source: &str) -> String {
+        let span = match self.span {
+            Some(span) => {
+                let SourceSpan { span, start, end } =
+                    LineIndex::new(source).resolve(widen(source, span));
+
+                format!(
+                    "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"end_line\":{},\"end_column\":{}}}",
+                    span.start, span.end, start.line, start.column, end.line, end.column
+                )
+            }
+            None => "null".to_string(),
+        };
+
+        format!(
+            "{{\"severity\":\"error\",\"message\":{},\"span\":{},\"label\":{},\"expected\":{},\"found\":{},\"notes\":{},\"help\":{}}}",
+            json_string(&self.message),
+            span,
+            json_option(self.label.as_deref()),
+            json_array(&self.expected),
+            json_option(self.found.as_deref()),
+            json_array(&self.notes),
+            json_option(self.help.as_deref()),
+        )
+    }
+}
+
+/// Returns the token starting at `offset`: an identifier or number, or else a single
+/// character. Empty at the end of `source`.
+pub(crate) fn token_at(source: &str, offset: usize) -> &str {
+    let rest = source.get(offset..).unwrap_or("");
+    let is_word = |char: char| char.is_alphanumeric() || char == '_';
+
+    let length = match rest.chars().next() {
+        None => 0,
+        Some(first) if is_word(first) => rest.find(|char| !is_word(char)).unwrap_or(rest.len()),
+        Some(first) => first.len_utf8(),
+    };
+
+    &rest[..length]
+}
+
+/// Extends a zero-width span over the token it points at.
+fn widen(source: &str, span: Span) -> Span {
+    let start = span.start.min(source.len());
+
+    if span.end > start {
+        return Span::new(start, span.end.min(source.len()));
+    }
+
+    Span::new(start, start + token_at(source, start).len())
+}
+
+fn json_string(value: &str) -> String {
+    let mut output = String::with_capacity(value.len() + 2);
+    output.push('"');
+
+    for char in value.chars() {
+        match char {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            char if char.is_control() => {
+                let _ = write!(output, "\\u{:04x}", char as u32);
+            }
+            char => output.push(char),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+fn json_option(value: Option<&str>) -> String {
+    value.map(json_string).unwrap_or_else(|| "null".to_string())
+}
+
+fn json_array(values: &[String]) -> String {
+    let values: Vec<String> = values.iter().map(|value| json_string(value)).collect();
+    format!("[{}]", values.join(","))
+}
@@ -1,13 +1,33 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
 use std::str::FromStr;
 
 use crate::bi_operator::BiOperator;
-use crate::runtime::{ExecutionContext, RuntimeError};
-use crate::value::Value;
+use crate::format;
+use crate::math::{self, MathFunction};
+use crate::runtime::{Arity, ExecutionContext, RuntimeError};
+use crate::span::SourceSpan;
+use crate::unary_operator::UnaryOperator;
+use crate::value::{Type, Value};
 
 #[derive(Debug, Clone)]
-pub enum Expression {
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExpressionKind {
     Number(f64),
+    Integer(i64),
     Boolean(bool),
+    Str(Rc<str>),
+    Array(Vec<Expression>),
+    /// `{ key: value, ... }`, entries in source order.
+    Map(Vec<(String, Expression)>),
+    /// `collection[index]`, also written `collection.key` for maps.
+    Index(Box<Expression>, Box<Expression>),
+    Unary(UnaryOperator, Box<Expression>),
     BinaryExpression(Box<Expression>, BiOperator, Box<Expression>),
     BracketExpression(Box<Expression>),
     Function(String, Vec<Expression>),
@@ -15,46 +35,219 @@ pub enum Expression {
 }
 
 impl Expression {
+    pub fn new(kind: ExpressionKind, span: SourceSpan) -> Self {
+        Self { kind, span }
+    }
+
+    /// Evaluates the expression; errors not located yet get this expression's span.
     pub fn evaluate(&self, context: &mut ExecutionContext) -> Result<Value, RuntimeError> {
-        match self {
-            Expression::Number(number) => Ok(Value::from(*number)),
-            Expression::Boolean(boolean) => Ok(Value::from(*boolean)),
-            Expression::BracketExpression(expression) => expression.evaluate(context),
-            Expression::BinaryExpression(left, operator, right) => {
-                let left = left.evaluate(context)?;
-                let right = right.evaluate(context)?;
-
-                Ok(operator.apply(left, right)?)
+        self.evaluate_kind(context)
+            .map_err(|error| error.at(self.span))
+    }
+
+    fn evaluate_kind(&self, context: &mut ExecutionContext) -> Result<Value, RuntimeError> {
+        match &self.kind {
+            ExpressionKind::Number(number) => Ok(Value::from(*number)),
+            ExpressionKind::Integer(integer) => Ok(Value::from(*integer)),
+            ExpressionKind::Boolean(boolean) => Ok(Value::from(*boolean)),
+            ExpressionKind::Str(string) => Ok(Value::Str(string.clone())),
+            ExpressionKind::BracketExpression(expression) => expression.evaluate(context),
+            ExpressionKind::Array(elements) => {
+                Self::evaluate_arguments(elements, context).map(Value::from)
             }
-            Expression::Function(name, arguments) => {
-                let function = Function::from_str(name)?;
+            ExpressionKind::Map(entries) => Self::evaluate_map(entries, context),
+            ExpressionKind::Index(array, index) => Self::evaluate_index(array, index, context),
+            ExpressionKind::Unary(operator, operand) => {
+                Self::evaluate_unary(operator, operand, context)
+            }
+            ExpressionKind::BinaryExpression(left, operator, right) => {
+                Self::evaluate_binary(left, operator, right, context)
+            }
+            ExpressionKind::Function(name, arguments) => {
+                self.evaluate_call(name, arguments, context)
+            }
+            ExpressionKind::Variable(name) => Self::evaluate_variable(name, context),
+        }
+    }
 
-                let arguments = arguments
-                    .iter()
-                    .map(|argument| argument.evaluate(context))
-                    .collect::<Result<Vec<Value>, RuntimeError>>()?;
+    // Arms with locals of their own live in separate functions: a debug build reserves
+    // stack for every arm's locals in each frame of `evaluate_kind`, and recursive
+    // calls nest one of those frames per level of the expression.
 
-                Ok(function.apply(arguments.as_slice(), context)?)
-            }
-            Expression::Variable(name) => {
-                if let Some(value) = context.get_variable(name) {
-                    Ok(*value)
-                } else {
-                    Err(format!("Unknown variable: {}", name).into())
-                }
+    fn evaluate_map(
+        entries: &[(String, Expression)],
+        context: &mut ExecutionContext,
+    ) -> Result<Value, RuntimeError> {
+        let mut map = BTreeMap::new();
+
+        for (key, value) in entries {
+            map.insert(key.clone(), value.evaluate(context)?);
+        }
+
+        Ok(map.into())
+    }
+
+    fn evaluate_index(
+        array: &Expression,
+        index: &Expression,
+        context: &mut ExecutionContext,
+    ) -> Result<Value, RuntimeError> {
+        let array = array.evaluate(context)?;
+        let index = index.evaluate(context)?;
+
+        array.index(&index)
+    }
+
+    fn evaluate_unary(
+        operator: &UnaryOperator,
+        operand: &Expression,
+        context: &mut ExecutionContext,
+    ) -> Result<Value, RuntimeError> {
+        let operand = operand.evaluate(context)?;
+
+        operator.apply(operand)
+    }
+
+    fn evaluate_binary(
+        left: &Expression,
+        operator: &BiOperator,
+        right: &Expression,
+        context: &mut ExecutionContext,
+    ) -> Result<Value, RuntimeError> {
+        let left = left.evaluate(context)?;
+
+        if let (Some(decisive), Value::Boolean(boolean)) = (operator.short_circuit(), &left) {
+            if *boolean == decisive {
+                return Ok(left);
             }
         }
+
+        let right = right.evaluate(context)?;
+
+        operator.apply(left, right)
+    }
+
+    fn evaluate_call(
+        &self,
+        name: &str,
+        arguments: &[Expression],
+        context: &mut ExecutionContext,
+    ) -> Result<Value, RuntimeError> {
+        let arguments = Self::evaluate_arguments(arguments, context)?;
+
+        Interpreter {
+            context,
+            call_site: self.span,
+        }
+        .call(name, arguments)
+    }
+
+    fn evaluate_variable(
+        name: &String,
+        context: &mut ExecutionContext,
+    ) -> Result<Value, RuntimeError> {
+        if let Some(value) = context.get_variable(name) {
+            Ok(value.clone())
+        } else if context.get_function(name).is_some() || is_native(name, context) {
+            Ok(Value::Function(name.as_str().into()))
+        } else if let Some(value) = math::constant(name) {
+            Ok(value)
+        } else {
+            Err(format!("Unknown variable: {}", name).into())
+        }
+    }
+
+    fn evaluate_arguments(
+        arguments: &[Expression],
+        context: &mut ExecutionContext,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        arguments
+            .iter()
+            .map(|argument| argument.evaluate(context))
+            .collect()
     }
 }
 
-enum Function {
-    Sin,
-    Cos,
-    Tan,
-    Asin,
-    Acos,
-    Atan,
+/// Calls functions by name on behalf of builtins such as `map`, in whichever execution
+/// engine is running.
+pub(crate) trait Caller {
+    fn context(&mut self) -> &mut ExecutionContext;
+
+    fn call(&mut self, name: &str, arguments: Vec<Value>) -> Result<Value, RuntimeError>;
+}
+
+/// The tree-walking interpreter calling from an expression at `call_site`.
+struct Interpreter<'a> {
+    context: &'a mut ExecutionContext,
+    call_site: SourceSpan,
+}
+
+impl Caller for Interpreter<'_> {
+    fn context(&mut self) -> &mut ExecutionContext {
+        self.context
+    }
+
+    fn call(&mut self, name: &str, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        if let Some(function) = self.context.get_function(name) {
+            return function.call(arguments, self.call_site, self.context);
+        }
+
+        call_native(name, &arguments, self)
+    }
+}
+
+/// Whether `name` is a host function or builtin.
+pub(crate) fn is_native(name: &str, context: &ExecutionContext) -> bool {
+    context.get_host_function(name).is_some() || Function::from_str(name).is_ok()
+}
+
+/// Calls a host function or builtin by name; user functions are resolved by each
+/// execution engine before falling back to this.
+pub(crate) fn call_native(
+    name: &str,
+    arguments: &[Value],
+    caller: &mut dyn Caller,
+) -> Result<Value, RuntimeError> {
+    if let Some((arity, function)) = caller.context().get_host_function(name) {
+        check_arity(name, arity, arguments.len())?;
+
+        return function(arguments);
+    }
+
+    let function = Function::from_str(name)?;
+    check_arity(name, function.arity(), arguments.len())?;
+
+    function.apply(arguments, caller)
+}
+
+pub(crate) fn check_arity(name: &str, arity: Arity, count: usize) -> Result<(), RuntimeError> {
+    if arity.accepts(count) {
+        return Ok(());
+    }
+
+    Err(format!("Function `{}` expects {}, got {}", name, arity, count).into())
+}
+
+pub(crate) enum Function {
+    Math(MathFunction),
+    /// Writes its arguments separated by spaces, then ends the line.
     Print,
+    /// Same as `print`.
+    Println,
+    /// Like `print`, to the error output.
+    Eprint,
+    /// Same as `eprint`.
+    Eprintln,
+    Format,
+    Len,
+    Push,
+    Pop,
+    Slice,
+    Map,
+    Sum,
+    Has,
+    Keys,
+    Remove,
 }
 
 impl FromStr for Function {
@@ -62,76 +255,209 @@ impl FromStr for Function {
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         match value {
-            "sin" => Ok(Function::Sin),
-            "cos" => Ok(Function::Cos),
-            "tan" => Ok(Function::Tan),
-            "asin" => Ok(Function::Asin),
-            "acos" => Ok(Function::Acos),
-            "atan" => Ok(Function::Atan),
             "print" => Ok(Function::Print),
-            _ => Err(format!("Unknown function: {}", value)),
+            "println" => Ok(Function::Println),
+            "eprint" => Ok(Function::Eprint),
+            "eprintln" => Ok(Function::Eprintln),
+            "format" => Ok(Function::Format),
+            "len" => Ok(Function::Len),
+            "push" => Ok(Function::Push),
+            "pop" => Ok(Function::Pop),
+            "slice" => Ok(Function::Slice),
+            "map" => Ok(Function::Map),
+            "sum" => Ok(Function::Sum),
+            "has" => Ok(Function::Has),
+            "keys" => Ok(Function::Keys),
+            "remove" => Ok(Function::Remove),
+            _ => value.parse().map(Function::Math),
         }
     }
 }
 
 impl Function {
-    fn apply(
-        &self,
-        arguments: &[Value],
-        context: &mut ExecutionContext,
-    ) -> Result<Value, RuntimeError> {
+    /// Parameter types, or `None` for functions accepting any arguments.
+    pub(crate) fn parameters(&self) -> Option<&'static [Type]> {
+        match self {
+            Function::Print | Function::Println | Function::Eprint | Function::Eprintln => None,
+            Function::Format => Some(&[Type::Str, Type::Any]),
+            Function::Len => Some(&[Type::Any]),
+            Function::Push => Some(&[Type::Array, Type::Any]),
+            Function::Pop | Function::Sum => Some(&[Type::Array]),
+            Function::Slice => Some(&[Type::Array, Type::Number, Type::Number]),
+            Function::Map => Some(&[Type::Array, Type::Function]),
+            Function::Has | Function::Remove => Some(&[Type::Map, Type::Str]),
+            Function::Keys => Some(&[Type::Map]),
+            Function::Math(function) => Some(function.parameters()),
+        }
+    }
+
+    /// Number of arguments accepted, checked before the builtin runs.
+    pub(crate) fn arity(&self) -> Arity {
+        match self {
+            Function::Math(function) => function.arity(),
+            Function::Format => Arity::AtLeast(1),
+            _ => match self.parameters() {
+                Some(parameters) => Arity::Exact(parameters.len()),
+                None => Arity::Any,
+            },
+        }
+    }
+
+    pub(crate) fn return_type(&self) -> Type {
+        match self {
+            Function::Print
+            | Function::Println
+            | Function::Eprint
+            | Function::Eprintln
+            | Function::Push => Type::Void,
+            Function::Format => Type::Str,
+            Function::Pop | Function::Remove => Type::Any,
+            Function::Slice | Function::Map | Function::Keys => Type::Array,
+            Function::Has => Type::Boolean,
+            _ => Type::Number,
+        }
+    }
+
+    fn apply(&self, arguments: &[Value], caller: &mut dyn Caller) -> Result<Value, RuntimeError> {
         use Function::*;
-        use Value::Numerical;
+        use Value::{Array, Integer, Numerical};
 
         let result = match self {
-            Sin => {
-                if let [Numerical(number)] = arguments {
-                    number.sin().into()
+            Math(function) => function.apply(arguments, caller.context().domain_policy())?,
+            Print | Println | Eprint | Eprintln => {
+                let text = format::join(arguments) + "\n";
+
+                match self {
+                    Eprint | Eprintln => caller.context().write_error(&text)?,
+                    _ => caller.context().write(&text)?,
+                }
+
+                Value::Void
+            }
+            Format => {
+                if let [Value::Str(template), arguments @ ..] = arguments {
+                    format::format(template, arguments)?.into()
+                } else {
+                    return Err("Invalid arguments for format function".to_string().into());
+                }
+            }
+            Len => match arguments {
+                [Array(elements)] => elements.borrow().len().into(),
+                [Value::Str(string)] => string.chars().count().into(),
+                [Value::Map(entries)] => entries.borrow().len().into(),
+                _ => return Err("Invalid arguments for len function".to_string().into()),
+            },
+            Push => {
+                if let [Array(elements), value] = arguments {
+                    elements.borrow_mut().push(value.clone());
+                    Value::Void
                 } else {
-                    return Err("Invalid arguments for sin function".to_string().into());
+                    return Err("Invalid arguments for push function".to_string().into());
                 }
             }
-            Cos => {
-                if let [Numerical(number)] = arguments {
-                    number.cos().into()
+            Pop => {
+                if let [Array(elements)] = arguments {
+                    elements
+                        .borrow_mut()
+                        .pop()
+                        .ok_or_else(|| "Cannot pop from an empty array".to_string())?
                 } else {
-                    return Err("Invalid arguments for cos function".to_string().into());
+                    return Err("Invalid arguments for pop function".to_string().into());
                 }
             }
-            Tan => {
-                if let [Numerical(number)] = arguments {
-                    number.tan().into()
+            Slice => {
+                if let [Array(elements), start, end] = arguments {
+                    let elements = elements.borrow();
+                    let length = elements.len();
+
+                    // Unlike indices, slice bounds may point one past the last element.
+                    let bound = |value: &Value| match value {
+                        Integer(integer) if (0..=length as i64).contains(integer) => {
+                            Ok(*integer as usize)
+                        }
+                        Numerical(number)
+                            if number.fract() == 0.0 && (0.0..=length as f64).contains(number) =>
+                        {
+                            Ok(*number as usize)
+                        }
+                        value => Err(RuntimeError::from(format!(
+                            "Slice bound {} out of range for array of length {}",
+                            value, length
+                        ))),
+                    };
+                    let (start, end) = (bound(start)?, bound(end)?);
+
+                    if start > end {
+                        return Err(
+                            format!("Slice start {} is after its end {}", start, end).into()
+                        );
+                    }
+
+                    elements[start..end].to_vec().into()
                 } else {
-                    return Err("Invalid arguments for tan function".to_string().into());
+                    return Err("Invalid arguments for slice function".to_string().into());
                 }
             }
-            Asin => {
-                if let [Numerical(number)] = arguments {
-                    number.asin().into()
+            // Kept out of this frame, which recursive callbacks would nest.
+            Map => Self::map(arguments, caller)?,
+            Sum => {
+                if let [Array(elements)] = arguments {
+                    let mut total = Integer(0);
+
+                    for element in elements.borrow().iter() {
+                        if element.as_number().is_none() {
+                            return Err(format!("Expected a number, got {:?}", element).into());
+                        }
+
+                        total = BiOperator::Add.apply(total, element.clone())?;
+                    }
+
+                    total
                 } else {
-                    return Err("Invalid arguments for asin function".to_string().into());
+                    return Err("Invalid arguments for sum function".to_string().into());
                 }
             }
-            Acos => {
-                if let [Numerical(number)] = arguments {
-                    number.acos().into()
+            Has => {
+                if let [Value::Map(entries), Value::Str(key)] = arguments {
+                    entries.borrow().contains_key(key.as_ref()).into()
                 } else {
-                    return Err("Invalid arguments for acos function".to_string().into());
+                    return Err("Invalid arguments for has function".to_string().into());
                 }
             }
-            Atan => {
-                if let [Numerical(number)] = arguments {
-                    number.atan().into()
+            Keys => {
+                if let [map @ Value::Map(_)] = arguments {
+                    map.iteration()?.into()
                 } else {
-                    return Err("Invalid arguments for atan function".to_string().into());
+                    return Err("Invalid arguments for keys function".to_string().into());
                 }
             }
-            Print => {
-                context.write(arguments);
-                Value::Void
+            Remove => {
+                if let [Value::Map(entries), Value::Str(key)] = arguments {
+                    entries
+                        .borrow_mut()
+                        .remove(key.as_ref())
+                        .ok_or_else(|| format!("Key {:?} not found in map", key))?
+                } else {
+                    return Err("Invalid arguments for remove function".to_string().into());
+                }
             }
         };
 
         Ok(result)
     }
+
+    fn map(arguments: &[Value], caller: &mut dyn Caller) -> Result<Value, RuntimeError> {
+        if let [Value::Array(elements), Value::Function(function)] = arguments {
+            // The callback may modify the array while it is mapped.
+            let elements = elements.borrow().clone();
+
+            Ok(elements
+                .into_iter()
+                .map(|element| caller.call(function, vec![element]))
+                .collect::<Result<Vec<Value>, RuntimeError>>()?
+                .into())
+        } else {
+            Err("Invalid arguments for map function".to_string().into())
+        }
+    }
 }
@@ -0,0 +1,244 @@
+use crate::runtime::{Arity, RuntimeError};
+use crate::value::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// Largest width or precision a placeholder may ask for, so that a stray digit cannot
+/// exhaust memory.
+const MAX_SIZE: usize = 65535;
+
+/// A parsed `{:...}` placeholder: `[[fill]align][+][0][width][.precision][?]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Spec {
+    fill: char,
+    /// Numbers align right by default and everything else left.
+    alignment: Option<Alignment>,
+    sign: bool,
+    /// Pads numbers with zeros after their sign, ignoring fill and alignment.
+    zero: bool,
+    width: Option<usize>,
+    /// Decimal places of numbers, or the maximum number of characters of other values.
+    precision: Option<usize>,
+    debug: bool,
+}
+
+impl Spec {
+    const PLAIN: Spec = Spec {
+        fill: ' ',
+        alignment: None,
+        sign: false,
+        zero: false,
+        width: None,
+        precision: None,
+        debug: false,
+    };
+
+    fn parse(spec: &str) -> Option<Spec> {
+        let mut result = Spec::PLAIN;
+        let mut chars: Vec<char> = spec.chars().collect();
+
+        if let Some(debug) = chars.strip_suffix(&['?']) {
+            result.debug = true;
+            chars = debug.to_vec();
+        }
+
+        let mut rest = &chars[..];
+
+        match rest {
+            [fill, align, tail @ ..] if alignment(*align).is_some() => {
+                result.fill = *fill;
+                result.alignment = alignment(*align);
+                rest = tail;
+            }
+            [align, tail @ ..] if alignment(*align).is_some() => {
+                result.alignment = alignment(*align);
+                rest = tail;
+            }
+            _ => {}
+        }
+
+        if let ['+', tail @ ..] = rest {
+            result.sign = true;
+            rest = tail;
+        }
+
+        if let ['0', tail @ ..] = rest {
+            result.zero = true;
+            rest = tail;
+        }
+
+        let (width, tail) = digits(rest)?;
+        result.width = width;
+        rest = tail;
+
+        if let ['.', tail @ ..] = rest {
+            let (precision, tail) = digits(tail)?;
+            result.precision = Some(precision?);
+            rest = tail;
+        }
+
+        rest.is_empty().then_some(result)
+    }
+}
+
+fn alignment(char: char) -> Option<Alignment> {
+    match char {
+        '<' => Some(Alignment::Left),
+        '^' => Some(Alignment::Center),
+        '>' => Some(Alignment::Right),
+        _ => None,
+    }
+}
+
+/// Reads a leading decimal number, if any, and returns the characters after it;
+/// `None` if the number overflows.
+fn digits(chars: &[char]) -> Option<(Option<usize>, &[char])> {
+    let length = chars
+        .iter()
+        .take_while(|char| char.is_ascii_digit())
+        .count();
+    let number: String = chars[..length].iter().collect();
+
+    let number = match length {
+        0 => None,
+        _ => Some(number.parse().ok()?),
+    };
+
+    Some((number, &chars[length..]))
+}
+
+/// Joins values as `print` writes them: separated by single spaces.
+pub(crate) fn join(values: &[Value]) -> String {
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+
+    values.join(" ")
+}
+
+/// Writes a value as the REPL echoes it and `{:?}` formats it, with strings quoted.
+pub(crate) fn debug(value: &Value) -> String {
+    match value {
+        Value::Str(string) => format!("{:?}", string),
+        value => value.to_string(),
+    }
+}
+
+/// Substitutes `arguments` into the `{}` placeholders of `template`, in order.
+///
+/// `{{` and `}}` stand for literal braces. Every argument must be used exactly once.
+pub(crate) fn format(template: &str, arguments: &[Value]) -> Result<String, RuntimeError> {
+    let mut result = String::new();
+    let mut placeholders = Vec::new();
+    let mut chars = template.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                result.push('}');
+            }
+            '{' => {
+                let Some(end) = chars.as_str().find('}') else {
+                    return Err("Unclosed `{` in format string".to_string().into());
+                };
+
+                let placeholder = &chars.as_str()[..end];
+                let spec = match placeholder.strip_prefix(':') {
+                    Some(spec) => Spec::parse(spec),
+                    None if placeholder.is_empty() => Some(Spec::PLAIN),
+                    None => None,
+                };
+                let Some(spec) = spec else {
+                    return Err(format!("Invalid format placeholder `{{{}}}`", placeholder).into());
+                };
+
+                for (option, size) in [("width", spec.width), ("precision", spec.precision)] {
+                    if let Some(size) = size.filter(|size| *size > MAX_SIZE) {
+                        return Err(format!(
+                            "Format {} {} exceeds the maximum of {}",
+                            option, size, MAX_SIZE
+                        )
+                        .into());
+                    }
+                }
+
+                placeholders.push((result.len(), spec));
+                chars = chars.as_str()[end + 1..].chars();
+            }
+            '}' => return Err("Unmatched `}` in format string".to_string().into()),
+            char => result.push(char),
+        }
+    }
+
+    if placeholders.len() != arguments.len() {
+        return Err(format!(
+            "Format string expects {}, got {}",
+            Arity::Exact(placeholders.len()),
+            arguments.len()
+        )
+        .into());
+    }
+
+    // Insert from the back so that the recorded positions stay valid.
+    for ((position, spec), argument) in placeholders.iter().zip(arguments).rev() {
+        result.insert_str(*position, &format_value(argument, spec));
+    }
+
+    Ok(result)
+}
+
+fn format_value(value: &Value, spec: &Spec) -> String {
+    let is_number = matches!(value, Value::Integer(_) | Value::Numerical(_));
+
+    let mut text = match (value, spec.precision) {
+        (Value::Integer(integer), Some(0)) => integer.to_string(),
+        (Value::Integer(integer), Some(precision)) => {
+            format!("{}.{}", integer, "0".repeat(precision))
+        }
+        (Value::Numerical(number), Some(precision)) => format!("{:.*}", precision, number),
+        (value, Some(precision)) if spec.debug => debug(value).chars().take(precision).collect(),
+        (value, Some(precision)) => value.to_string().chars().take(precision).collect(),
+        (value, None) if spec.debug => debug(value),
+        (value, None) => value.to_string(),
+    };
+
+    if is_number && spec.sign && !text.starts_with('-') && text != "NaN" {
+        text.insert(0, '+');
+    }
+
+    let width = spec.width.unwrap_or(0);
+    let padding = width.saturating_sub(text.chars().count());
+
+    if padding == 0 {
+        return text;
+    }
+
+    if is_number && spec.zero {
+        let sign = if text.starts_with(['+', '-']) { 1 } else { 0 };
+        text.insert_str(sign, &"0".repeat(padding));
+
+        return text;
+    }
+
+    let alignment = spec.alignment.unwrap_or(if is_number {
+        Alignment::Right
+    } else {
+        Alignment::Left
+    });
+    let (before, after) = match alignment {
+        Alignment::Left => (0, padding),
+        Alignment::Center => (padding / 2, padding - padding / 2),
+        Alignment::Right => (padding, 0),
+    };
+    let fill = |count: usize| spec.fill.to_string().repeat(count);
+
+    format!("{}{}{}", fill(before), text, fill(after))
+}
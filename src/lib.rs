@@ -1,6 +1,15 @@
 mod bi_operator;
+pub mod bytecode;
 pub mod compiler;
+pub mod diagnostic;
 mod expression;
+mod format;
+mod math;
+pub mod repl;
 pub mod runtime;
+pub mod span;
 mod statement;
+mod type_checker;
+mod unary_operator;
 pub mod value;
+mod vm;
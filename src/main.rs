@@ -1,19 +1,18 @@
-use crate::compiler::MeadorCompiler;
-use compiler::CompilationError;
-use runtime::{ExecutionContext, RuntimeError};
+use meadorc::compiler::{CompilationError, MeadorCompiler};
+use meadorc::diagnostic::Diagnostic;
+use meadorc::repl::{Repl, ReplError};
+use meadorc::runtime::{ExecutionContext, RuntimeError};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
 
-mod bi_operator;
-mod compiler;
-mod expression;
-mod runtime;
-mod statement;
-mod value;
+const HISTORY_FILE: &str = ".meadorc_history";
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     RuntimeError(RuntimeError),
     CompileError(CompilationError),
     InvalidSourcePath(String),
+    LineEditor(ReadlineError),
 }
 
 impl std::fmt::Display for Error {
@@ -22,6 +21,7 @@ impl std::fmt::Display for Error {
             Error::RuntimeError(error) => write!(f, "{}", error),
             Error::CompileError(error) => write!(f, "{}", error),
             Error::InvalidSourcePath(path) => write!(f, "Invalid source path: {}", path),
+            Error::LineEditor(error) => write!(f, "Line editor error: {}", error),
         }
     }
 }
@@ -38,18 +38,104 @@ impl From<CompilationError> for Error {
     }
 }
 
+impl From<ReadlineError> for Error {
+    fn from(error: ReadlineError) -> Error {
+        Error::LineEditor(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
 pub fn main() -> Result<(), Error> {
-    let code = std::env::args()
-        .nth(1)
-        .ok_or_else(|| Error::InvalidSourcePath("No source file provided".to_string()))
-        .map(std::path::PathBuf::from)
-        .and_then(|path| {
-            std::fs::read_to_string(path)
-                .map_err(|_| Error::InvalidSourcePath("Failed to read source file".to_string()))
-        })?;
-
-    let program = MeadorCompiler::compile(&code).map_err(Error::from)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
-
-    program.execute(&mut context).map_err(Error::from)
+    let mut path = None;
+    let mut error_format = ErrorFormat::Human;
+
+    for argument in std::env::args().skip(1) {
+        match argument.as_str() {
+            "--repl" => path = None,
+            "--error-format=human" => error_format = ErrorFormat::Human,
+            "--error-format=json" => error_format = ErrorFormat::Json,
+            _ => path = Some(argument),
+        }
+    }
+
+    match path {
+        Some(path) => run_file(&path, error_format),
+        None => run_repl(),
+    }
+}
+
+fn run_file(path: &str, error_format: ErrorFormat) -> Result<(), Error> {
+    let code = std::fs::read_to_string(path)
+        .map_err(|_| Error::InvalidSourcePath("Failed to read source file".to_string()))?;
+
+    let program = match MeadorCompiler::compile(&code) {
+        Ok(program) => program,
+        Err(error) => {
+            report(&error.diagnostics(), &code, error_format);
+            std::process::exit(1);
+        }
+    };
+    let mut context = ExecutionContext::stdout();
+
+    if let Err(error) = program.to_bytecode().execute(&mut context) {
+        report(&[error.diagnostic()], &code, error_format);
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Prints diagnostics to stderr, JSON ones one per line.
+fn report(diagnostics: &[Diagnostic], code: &str, error_format: ErrorFormat) {
+    for diagnostic in diagnostics {
+        match error_format {
+            ErrorFormat::Human => eprint!("{}", diagnostic.render(code)),
+            ErrorFormat::Json => eprintln!("{}", diagnostic.to_json(code)),
+        }
+    }
+}
+
+fn run_repl() -> Result<(), Error> {
+    let mut editor = DefaultEditor::new()?;
+    let history =
+        std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(HISTORY_FILE));
+
+    if let Some(history) = &history {
+        // There is no history yet on the first run.
+        let _ = editor.load_history(history);
+    }
+
+    let mut repl = Repl::new(ExecutionContext::stdout());
+
+    loop {
+        let prompt = if repl.is_pending() { "... " } else { ">>> " };
+
+        match editor.readline(prompt) {
+            Ok(line) => {
+                editor.add_history_entry(line.as_str())?;
+
+                match repl.feed(&line) {
+                    Err(ReplError::Compile { error, input }) => {
+                        report(&error.diagnostics(), &input, ErrorFormat::Human)
+                    }
+                    Err(error) => eprintln!("{}", error),
+                    Ok(_) => {}
+                }
+            }
+            Err(ReadlineError::Interrupted) => repl.reset(),
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    if let Some(history) = &history {
+        editor.save_history(history)?;
+    }
+
+    Ok(())
 }
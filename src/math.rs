@@ -0,0 +1,221 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::runtime::{Arity, DomainPolicy, RuntimeError};
+use crate::value::{Type, Value};
+
+/// The value of a builtin constant such as `PI`; variables of the same name shadow it.
+pub(crate) fn constant(name: &str) -> Option<Value> {
+    let value = match name {
+        "PI" => std::f64::consts::PI,
+        "E" => std::f64::consts::E,
+        "INF" => f64::INFINITY,
+        _ => return None,
+    };
+
+    Some(value.into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MathFunction {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sqrt,
+    Exp,
+    Ln,
+    Log10,
+    Log2,
+    Hypot,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    /// Rounds half-way cases away from zero.
+    Round,
+    Trunc,
+    Min,
+    Max,
+    Clamp,
+}
+
+impl FromStr for MathFunction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        use MathFunction::*;
+
+        let function = match value {
+            "sin" => Sin,
+            "cos" => Cos,
+            "tan" => Tan,
+            "asin" => Asin,
+            "acos" => Acos,
+            "atan" => Atan,
+            "atan2" => Atan2,
+            "sqrt" => Sqrt,
+            "exp" => Exp,
+            "ln" => Ln,
+            "log10" => Log10,
+            "log2" => Log2,
+            "hypot" => Hypot,
+            "abs" => Abs,
+            "sign" => Sign,
+            "floor" => Floor,
+            "ceil" => Ceil,
+            "round" => Round,
+            "trunc" => Trunc,
+            "min" => Min,
+            "max" => Max,
+            "clamp" => Clamp,
+            _ => return Err(format!("Unknown function: {}", value)),
+        };
+
+        Ok(function)
+    }
+}
+
+impl MathFunction {
+    pub(crate) fn parameters(&self) -> &'static [Type] {
+        match self {
+            MathFunction::Atan2 | MathFunction::Hypot => &[Type::Number, Type::Number],
+            MathFunction::Clamp => &[Type::Number, Type::Number, Type::Number],
+            _ => &[Type::Number],
+        }
+    }
+
+    pub(crate) fn arity(&self) -> Arity {
+        match self {
+            MathFunction::Min | MathFunction::Max => Arity::AtLeast(1),
+            _ => Arity::Exact(self.parameters().len()),
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("{:?}", self).to_lowercase()
+    }
+
+    /// Applies the function to numbers.
+    ///
+    /// Integers stay integers where the result is always whole: `abs`, `sign`,
+    /// rounding, and `min`, `max` and `clamp`, which return one of their arguments
+    /// unchanged. Any other result is a float. Arguments outside the domain of the
+    /// function, for which it would give NaN from numbers that are not NaN, are
+    /// handled according to `policy`.
+    pub(crate) fn apply(
+        &self,
+        arguments: &[Value],
+        policy: DomainPolicy,
+    ) -> Result<Value, RuntimeError> {
+        use MathFunction::*;
+
+        let Some(numbers) = arguments
+            .iter()
+            .map(Value::as_number)
+            .collect::<Option<Vec<f64>>>()
+        else {
+            return Err(format!("Invalid arguments for {} function", self.name()).into());
+        };
+
+        let result = match (self, arguments) {
+            (Abs, [Value::Integer(integer)]) => {
+                return integer
+                    .checked_abs()
+                    .map(Value::Integer)
+                    .ok_or_else(|| format!("Integer overflow in `abs`: {}", integer).into())
+            }
+            (Sign, [Value::Integer(integer)]) => return Ok(Value::Integer(integer.signum())),
+            (Floor | Ceil | Round | Trunc, [integer @ Value::Integer(_)]) => {
+                return Ok(integer.clone())
+            }
+            (Min | Max, _) => return Ok(self.extremum(arguments)),
+            (Clamp, [value, low, high]) => match (
+                low.compare_numbers(high),
+                value.compare_numbers(low),
+                value.compare_numbers(high),
+            ) {
+                (None | Some(Ordering::Greater), _, _) => f64::NAN,
+                (_, Some(Ordering::Less), _) => return Ok(low.clone()),
+                (_, _, Some(Ordering::Greater)) => return Ok(high.clone()),
+                _ => return Ok(value.clone()),
+            },
+            _ => self.apply_floats(&numbers),
+        };
+
+        if result.is_nan() && !numbers.iter().any(|number| number.is_nan()) {
+            return match policy {
+                DomainPolicy::Nan => Ok(result.into()),
+                DomainPolicy::Error => {
+                    let arguments: Vec<String> =
+                        arguments.iter().map(|value| value.to_string()).collect();
+
+                    Err(format!(
+                        "Math domain error: `{}` is undefined for {}",
+                        self.name(),
+                        arguments.join(", ")
+                    )
+                    .into())
+                }
+            };
+        }
+
+        Ok(result.into())
+    }
+
+    fn apply_floats(&self, numbers: &[f64]) -> f64 {
+        use MathFunction::*;
+
+        match (self, numbers) {
+            (Sin, [x]) => x.sin(),
+            (Cos, [x]) => x.cos(),
+            (Tan, [x]) => x.tan(),
+            (Asin, [x]) => x.asin(),
+            (Acos, [x]) => x.acos(),
+            (Atan, [x]) => x.atan(),
+            (Atan2, [y, x]) => y.atan2(*x),
+            (Sqrt, [x]) => x.sqrt(),
+            (Exp, [x]) => x.exp(),
+            (Ln, [x]) => x.ln(),
+            (Log10, [x]) => x.log10(),
+            (Log2, [x]) => x.log2(),
+            (Hypot, [x, y]) => x.hypot(*y),
+            (Abs, [x]) => x.abs(),
+            // Unlike `f64::signum`, zero has no sign.
+            (Sign, [x]) if *x == 0.0 => 0.0,
+            (Sign, [x]) => x.signum(),
+            (Floor, [x]) => x.floor(),
+            (Ceil, [x]) => x.ceil(),
+            (Round, [x]) => x.round(),
+            (Trunc, [x]) => x.trunc(),
+            _ => unreachable!("arity of `{}` is checked before it is applied", self.name()),
+        }
+    }
+
+    /// The smallest or largest argument, or the first NaN among them. Integers and
+    /// floats are compared exactly.
+    fn extremum(&self, arguments: &[Value]) -> Value {
+        let mut best = &arguments[0];
+
+        for argument in arguments {
+            // Every argument is a number, so only NaN is unordered.
+            let Some(ordering) = argument.compare_numbers(best) else {
+                return argument.clone();
+            };
+
+            let better = match self {
+                MathFunction::Min => ordering == Ordering::Less,
+                _ => ordering == Ordering::Greater,
+            };
+
+            if better {
+                best = argument;
+            }
+        }
+
+        best.clone()
+    }
+}
@@ -0,0 +1,154 @@
+use thiserror::Error;
+
+use crate::compiler::{CompilationError, MeadorCompiler};
+use crate::format;
+use crate::runtime::{ExecutionContext, RuntimeError};
+use crate::value::Value;
+
+/// Outcome of feeding a line to a [`Repl`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    /// The input has unclosed `{` blocks and waits for more lines.
+    Incomplete,
+    /// The input was compiled and run.
+    Complete,
+}
+
+#[derive(Debug, Error)]
+pub enum ReplError {
+    /// Keeps the failed input so diagnostics can quote it.
+    #[error("{error}")]
+    Compile {
+        error: Box<CompilationError>,
+        input: String,
+    },
+    #[error("{0}")]
+    Runtime(#[from] RuntimeError),
+}
+
+/// Interactive session evaluating input line by line against one persistent context.
+///
+/// Inputs run on the tree-walking interpreter, so variables and functions declared by
+/// one input stay visible to the next. The value of a trailing bare expression is
+/// written to the context output in the `{:?}` form of `format`, with strings quoted,
+/// unless it is `Void`. A failing input is discarded
+/// without ending the session.
+pub struct Repl {
+    context: ExecutionContext,
+    pending: String,
+}
+
+impl Repl {
+    pub fn new(context: ExecutionContext) -> Self {
+        Self {
+            context,
+            pending: String::new(),
+        }
+    }
+
+    pub fn context(&self) -> &ExecutionContext {
+        &self.context
+    }
+
+    /// Whether earlier lines are waiting for their blocks to be closed.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Discards the lines of an unfinished input.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn feed(&mut self, line: &str) -> Result<Input, ReplError> {
+        self.pending.push_str(line);
+        self.pending.push('\n');
+
+        if has_unclosed_block(&self.pending) {
+            return Ok(Input::Incomplete);
+        }
+
+        let code = std::mem::take(&mut self.pending);
+
+        if code.trim().is_empty() {
+            return Ok(Input::Complete);
+        }
+
+        let program = match MeadorCompiler::compile_interactive(&code, &self.context) {
+            Ok(program) => program,
+            Err(error) => {
+                return Err(ReplError::Compile {
+                    error: Box::new(error),
+                    input: code,
+                })
+            }
+        };
+        let value = program.evaluate(&mut self.context)?;
+
+        if value != Value::Void {
+            self.context
+                .write(&format!("{}\n", format::debug(&value)))?;
+        }
+
+        Ok(Input::Complete)
+    }
+}
+
+/// Counts braces outside of string literals and comments; an unclosed block comment
+/// also continues on the next line. Like the grammar, `//` is floor division directly
+/// after an operand and starts a line comment anywhere else.
+fn has_unclosed_block(code: &str) -> bool {
+    let mut depth = 0i32;
+    let mut comment_depth = 0i32;
+    let mut in_string = false;
+    let mut after_operand = false;
+    let mut chars = code.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        if comment_depth > 0 {
+            match (char, chars.peek()) {
+                ('/', Some('*')) => {
+                    chars.next();
+                    comment_depth += 1;
+                }
+                ('*', Some('/')) => {
+                    chars.next();
+                    comment_depth -= 1;
+                }
+                _ => {}
+            }
+
+            continue;
+        }
+
+        let was_after_operand = after_operand;
+
+        if !char.is_whitespace() {
+            after_operand = in_string
+                || char == '"'
+                || char.is_alphanumeric()
+                || matches!(char, '_' | '.' | ')' | ']');
+        }
+
+        match char {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            '/' if !in_string && !was_after_operand && chars.peek() == Some(&'/') => {
+                chars.find(|char| *char == '\n');
+                after_operand = false;
+            }
+            '/' if !in_string && chars.peek() == Some(&'*') => {
+                chars.next();
+                comment_depth += 1;
+                after_operand = was_after_operand;
+            }
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth > 0 || comment_depth > 0
+}
@@ -1,62 +1,441 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use std::rc::Rc;
 
+use crate::diagnostic::Diagnostic;
+use crate::span::SourceSpan;
+use crate::statement::UserFunction;
 use crate::value::Value;
 
+/// Default bound on nested user function calls in the tree-walking interpreter.
+///
+/// Every call recurses on the native stack; for ordinary function bodies this depth
+/// fits the 2 MiB stack of a spawned thread even in debug builds. See
+/// [`ExecutionContext::set_max_call_depth`].
+pub const MAX_CALL_DEPTH: usize = 150;
+
+/// Default bound on nested user function calls in the VM, whose calls only grow
+/// its own stacks.
+pub const MAX_VM_CALL_DEPTH: usize = 100_000;
+
+/// Lexical scopes of a single call, innermost last.
+type Frame = Vec<HashMap<String, Value>>;
+
+/// A function implemented in Rust and exposed to scripts by the embedder.
+pub type HostFunction = Rc<dyn Fn(&[Value]) -> Result<Value, RuntimeError>>;
+
+/// Number of arguments a function accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+    Any,
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            Arity::Exact(expected) => count == *expected,
+            Arity::AtLeast(minimum) => count >= *minimum,
+            Arity::Any => true,
+        }
+    }
+}
+
+impl From<usize> for Arity {
+    fn from(count: usize) -> Self {
+        Arity::Exact(count)
+    }
+}
+
+impl std::fmt::Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Arity::Exact(1) => write!(f, "1 argument"),
+            Arity::Exact(count) => write!(f, "{} arguments", count),
+            Arity::AtLeast(1) => write!(f, "at least 1 argument"),
+            Arity::AtLeast(count) => write!(f, "at least {} arguments", count),
+            Arity::Any => write!(f, "any number of arguments"),
+        }
+    }
+}
+
+/// What math builtins do with arguments outside their domain, such as `sqrt(-1)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DomainPolicy {
+    /// Return NaN, as floating-point arithmetic does.
+    #[default]
+    Nan,
+    /// Fail with a [`RuntimeError`] naming the function and its arguments.
+    Error,
+}
+
 pub struct ExecutionContext {
-    variables: HashMap<String, Value>,
-    stdout: std::io::Stdout,
+    /// Call frames; the outermost scope of the first one holds the program's globals.
+    frames: Vec<Frame>,
+    functions: HashMap<String, Rc<UserFunction>>,
+    host_functions: HashMap<String, (Arity, HostFunction)>,
+    domain_policy: DomainPolicy,
+    /// Overrides the call depth limit of both execution engines.
+    max_call_depth: Option<usize>,
+    output: Box<dyn Write>,
+    error_output: Box<dyn Write>,
 }
 
 impl ExecutionContext {
-    pub fn new(stdout: std::io::Stdout) -> Self {
+    /// Creates a context whose `print` output goes to `output` and whose `eprint`
+    /// output goes to stderr.
+    pub fn new(output: impl Write + 'static) -> Self {
         Self {
-            variables: HashMap::new(),
-            stdout,
+            frames: vec![vec![HashMap::new()]],
+            functions: HashMap::new(),
+            host_functions: HashMap::new(),
+            domain_policy: DomainPolicy::default(),
+            max_call_depth: None,
+            output: Box::new(output),
+            error_output: Box::new(std::io::stderr()),
         }
     }
 
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+
+    /// Creates a context that discards all output.
+    pub fn null() -> Self {
+        let mut context = Self::new(std::io::sink());
+        context.set_error_output(std::io::sink());
+
+        context
+    }
+
+    /// Creates a context writing into an in-memory buffer that stays readable
+    /// through the returned handle.
+    pub fn buffered() -> (Self, OutputBuffer) {
+        let buffer = OutputBuffer::default();
+
+        (Self::new(buffer.clone()), buffer)
+    }
+
+    /// Declares a variable in the innermost scope, shadowing any outer binding.
     pub fn set_variable(&mut self, name: String, value: Value) {
-        self.variables.insert(name, value);
+        self.current_frame_mut()
+            .last_mut()
+            .unwrap()
+            .insert(name, value);
     }
 
+    /// Updates the nearest existing binding of a variable.
+    pub fn assign_variable(&mut self, name: &str, value: Value) -> Result<(), RuntimeError> {
+        let frame = self.frames.len() - 1;
+
+        let scope = self.frames[frame]
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.contains_key(name));
+
+        let slot = match scope {
+            Some(scope) => scope.get_mut(name),
+            None => self.frames[0][0].get_mut(name),
+        };
+
+        match slot {
+            Some(slot) => {
+                *slot = value;
+                Ok(())
+            }
+            None => Err(format!("Assignment to undeclared variable: {}", name).into()),
+        }
+    }
+
+    /// Looks a variable up from the innermost scope of the current call frame outwards,
+    /// then among the globals.
     pub fn get_variable(&self, name: &String) -> Option<&Value> {
-        self.variables.get(name)
+        self.frames
+            .last()
+            .unwrap()
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .or_else(|| self.frames[0][0].get(name))
     }
 
-    pub fn write(&mut self, value: &[Value]) {
-        use Value::*;
+    /// Looks up a dotted path such as `config.rate`: a variable followed by the fields
+    /// of the maps it holds.
+    pub fn get_path(&self, path: &str) -> Option<Value> {
+        let mut fields = path.split('.');
+        let mut value = self.get_variable(&fields.next()?.to_string())?.clone();
 
-        for value in value {
-            match value {
-                Numerical(number) => {
-                    write!(self.stdout, "{}", number).unwrap();
-                }
-                Boolean(boolean) => {
-                    write!(self.stdout, "{}", boolean).unwrap();
-                }
-                Void => {}
-            }
+        for field in fields {
+            value = match &value {
+                Value::Map(entries) => entries.borrow().get(field)?.clone(),
+                _ => return None,
+            };
+        }
+
+        Some(value)
+    }
+
+    pub(crate) fn set_function(&mut self, name: String, function: Rc<UserFunction>) {
+        self.functions.insert(name, function);
+    }
+
+    pub(crate) fn get_function(&self, name: &str) -> Option<Rc<UserFunction>> {
+        self.functions.get(name).cloned()
+    }
+
+    /// Names of the user and host functions defined so far.
+    pub(crate) fn function_names(&self) -> HashSet<String> {
+        self.functions
+            .keys()
+            .chain(self.host_functions.keys())
+            .cloned()
+            .collect()
+    }
+
+    /// Exposes a Rust closure to scripts under `name`.
+    ///
+    /// Host functions are looked up after the script's own `fn` declarations and
+    /// before the builtins, so they can both add new names and replace builtin ones.
+    /// Arguments are checked against `arity` before the closure runs.
+    pub fn register_function<F>(
+        &mut self,
+        name: impl Into<String>,
+        arity: impl Into<Arity>,
+        function: F,
+    ) where
+        F: Fn(&[Value]) -> Result<Value, RuntimeError> + 'static,
+    {
+        self.host_functions
+            .insert(name.into(), (arity.into(), Rc::new(function)));
+    }
+
+    pub(crate) fn get_host_function(&self, name: &str) -> Option<(Arity, HostFunction)> {
+        self.host_functions.get(name).cloned()
+    }
+
+    pub fn set_domain_policy(&mut self, policy: DomainPolicy) {
+        self.domain_policy = policy;
+    }
+
+    pub fn domain_policy(&self) -> DomainPolicy {
+        self.domain_policy
+    }
+
+    /// Limits nested user function calls to `depth` in both execution engines,
+    /// replacing [`MAX_CALL_DEPTH`] and [`MAX_VM_CALL_DEPTH`].
+    ///
+    /// Threads with a smaller stack than a main thread should lower the limit of the
+    /// tree-walking interpreter, and larger stacks allow raising it.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = Some(depth);
+    }
+
+    /// The call depth limit, or `default` if none was set.
+    pub(crate) fn max_call_depth(&self, default: usize) -> usize {
+        self.max_call_depth.unwrap_or(default)
+    }
+
+    pub(crate) fn push_frame(
+        &mut self,
+        locals: HashMap<String, Value>,
+    ) -> Result<(), RuntimeError> {
+        let limit = self.max_call_depth(MAX_CALL_DEPTH);
+
+        if self.frames.len() > limit {
+            return Err(format!("Maximum call depth of {} exceeded", limit).into());
         }
 
-        writeln!(self.stdout).unwrap();
+        self.frames.push(vec![locals]);
+        Ok(())
+    }
+
+    pub(crate) fn pop_frame(&mut self) {
+        self.frames.pop();
+    }
+
+    pub(crate) fn push_scope(&mut self) {
+        self.current_frame_mut().push(HashMap::new());
+    }
+
+    pub(crate) fn pop_scope(&mut self) {
+        self.current_frame_mut().pop();
+    }
+
+    fn current_frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    /// Redirects the output of `eprint` and `eprintln`.
+    pub fn set_error_output(&mut self, output: impl Write + 'static) {
+        self.error_output = Box::new(output);
+    }
+
+    /// Writes `text` as is, flushing it so that output without a trailing newline
+    /// appears at once.
+    pub fn write(&mut self, text: &str) -> Result<(), RuntimeError> {
+        Self::write_to(&mut self.output, text)
+    }
+
+    pub fn write_error(&mut self, text: &str) -> Result<(), RuntimeError> {
+        Self::write_to(&mut self.error_output, text)
+    }
+
+    fn write_to(output: &mut dyn Write, text: &str) -> Result<(), RuntimeError> {
+        output
+            .write_all(text.as_bytes())
+            .and_then(|_| output.flush())
+            .map_err(Self::output_error)
+    }
+
+    fn output_error(error: std::io::Error) -> RuntimeError {
+        format!("Failed to write output: {}", error).into()
+    }
+}
+
+/// Shared in-memory output sink, see [`ExecutionContext::buffered`].
+#[derive(Debug, Clone, Default)]
+pub struct OutputBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl OutputBuffer {
+    pub fn bytes(&self) -> Vec<u8> {
+        self.0.borrow().clone()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
 
+/// An error raised while running a program.
+///
+/// The details are boxed to keep the `Result`s returned at every level of evaluation
+/// small, which bounds the native stack used by deeply recursive scripts.
 #[derive(Debug)]
 pub struct RuntimeError {
+    details: Box<ErrorDetails>,
+}
+
+#[derive(Debug)]
+struct ErrorDetails {
     message: String,
+    span: Option<SourceSpan>,
+    trace: Vec<StackFrame>,
+}
+
+/// A user function call that was active when a [`RuntimeError`] was raised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    pub function: String,
+    pub call_site: SourceSpan,
+}
+
+impl std::fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "in `{}`, called at {}", self.function, self.call_site)
+    }
 }
 
 impl RuntimeError {
     pub fn new(message: String) -> Self {
-        Self { message }
+        Self {
+            details: Box::new(ErrorDetails {
+                message,
+                span: None,
+                trace: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.details.message
+    }
+
+    /// Source span of the innermost expression that failed, if known.
+    pub fn span(&self) -> Option<SourceSpan> {
+        self.details.span
+    }
+
+    /// User function calls active when the error was raised, innermost first.
+    pub fn trace(&self) -> &[StackFrame] {
+        &self.details.trace
+    }
+
+    /// Records that the error left the body of `function`, called at `call_site`.
+    pub(crate) fn called_from(mut self, function: &str, call_site: SourceSpan) -> Self {
+        self.details.trace.push(StackFrame {
+            function: function.to_string(),
+            call_site,
+        });
+        self
+    }
+
+    /// Locates the error at `span` unless a more precise location is already known.
+    pub(crate) fn at(mut self, span: SourceSpan) -> Self {
+        self.details.span.get_or_insert(span);
+        self
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let mut diagnostic = Diagnostic::error(format!("Runtime error: {}", self.details.message));
+
+        if let Some(span) = self.details.span {
+            diagnostic = diagnostic.with_span(span.span);
+        }
+
+        for frame in self.collapsed_trace() {
+            diagnostic = diagnostic.with_note(frame);
+        }
+
+        diagnostic
+    }
+
+    /// The trace with each run of identical frames, as left by deep recursion, written
+    /// once along with its length.
+    fn collapsed_trace(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut frames = self.details.trace.iter().peekable();
+
+        while let Some(frame) = frames.next() {
+            let mut count = 1;
+
+            while frames.next_if_eq(&frame).is_some() {
+                count += 1;
+            }
+
+            match count {
+                1 => lines.push(frame.to_string()),
+                count => lines.push(format!("{} ({} times)", frame, count)),
+            }
+        }
+
+        lines
     }
 }
 
 impl std::fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Runtime error: {}", self.message)
+        match self.details.span {
+            Some(span) => write!(f, "Runtime error at {}: {}", span, self.details.message)?,
+            None => write!(f, "Runtime error: {}", self.details.message)?,
+        }
+
+        for frame in self.collapsed_trace() {
+            write!(f, "\n    {}", frame)?;
+        }
+
+        Ok(())
     }
 }
 
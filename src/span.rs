@@ -0,0 +1,125 @@
+/// Byte range of a syntax node in the source it was compiled from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Smallest span covering both `self` and `other`.
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl<'a> From<pest::Span<'a>> for Span {
+    fn from(span: pest::Span<'a>) -> Self {
+        Span::new(span.start(), span.end())
+    }
+}
+
+/// 1-based line and column, the column counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A [`Span`] together with the lines and columns of both of its ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SourceSpan {
+    pub span: Span,
+    pub start: LineCol,
+    pub end: LineCol,
+}
+
+impl SourceSpan {
+    /// Smallest span covering both `self` and `other`.
+    pub fn to(&self, other: SourceSpan) -> SourceSpan {
+        let start = if self.span.start <= other.span.start {
+            self.start
+        } else {
+            other.start
+        };
+        let end = if self.span.end >= other.span.end {
+            self.end
+        } else {
+            other.end
+        };
+
+        SourceSpan {
+            span: self.span.to(other.span),
+            start,
+            end,
+        }
+    }
+}
+
+impl std::fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.start.line, self.start.column)
+    }
+}
+
+/// Maps byte offsets of a source to lines and columns.
+///
+/// Line starts are computed once, so resolving an offset is a binary search plus a
+/// walk over the characters of a single line.
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Resolves a byte offset, clamped to the end of the source.
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let column = self.source[self.line_starts[line]..offset].chars().count() + 1;
+
+        LineCol {
+            line: line + 1,
+            column,
+        }
+    }
+
+    pub fn resolve(&self, span: Span) -> SourceSpan {
+        SourceSpan {
+            span,
+            start: self.line_col(span.start),
+            end: self.line_col(span.end),
+        }
+    }
+
+    /// Text of a 1-based line, without its line terminator.
+    pub fn line(&self, line: usize) -> &'a str {
+        let Some(start) = self.line_starts.get(line.wrapping_sub(1)) else {
+            return "";
+        };
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+
+        self.source[*start..end].trim_end_matches('\r')
+    }
+}
@@ -1,58 +1,341 @@
-use crate::expression::Expression;
-use crate::runtime::ExecutionContext;
-use crate::runtime::RuntimeError;
+use std::rc::Rc;
+
+use crate::expression::{check_arity, Expression};
+use crate::runtime::{Arity, ExecutionContext, RuntimeError};
+use crate::span::SourceSpan;
 use crate::value::Value;
 
 #[derive(Debug)]
-pub enum Statement {
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: SourceSpan,
+    /// `///` comment of a declaration.
+    pub doc: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum StatementKind {
     FunctionCall(Expression),
+    Declaration(String, Expression),
     Assignment(String, Expression),
+    /// `collection[index] = value`, or `collection.key = value` for maps, where
+    /// `collection` may itself be an element of another collection.
+    IndexAssignment(Expression, Expression, Expression),
     Conditional(Expression, Box<Statement>, Option<Box<Statement>>),
     Loop(Expression, Box<Statement>),
+    /// `for name in start..end body`, counting up from `start` to `end` exclusive.
+    For(String, Expression, Expression, Box<Statement>),
+    /// `for name in collection body`, over the elements of an array or keys of a map.
+    ForEach(String, Expression, Box<Statement>),
+    Break,
+    Continue,
     CodeBlock(Vec<Statement>),
+    FunctionDeclaration(Rc<UserFunction>),
+    Return(Option<Expression>),
+}
+
+/// How control leaves a statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+#[derive(Debug)]
+pub struct UserFunction {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub body: Vec<Statement>,
+}
+
+impl UserFunction {
+    /// Calls the function from `call_site`, which is recorded in the trace of any error
+    /// raised by its body.
+    pub fn call(
+        &self,
+        arguments: Vec<Value>,
+        call_site: SourceSpan,
+        context: &mut ExecutionContext,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(
+            &self.name,
+            Arity::Exact(self.parameters.len()),
+            arguments.len(),
+        )?;
+
+        let locals = self.parameters.iter().cloned().zip(arguments).collect();
+        context.push_frame(locals)?;
+
+        let result = Statement::execute_all(&self.body, context);
+        context.pop_frame();
+
+        match result.map_err(|error| error.called_from(&self.name, call_site))? {
+            Flow::Return(value) => Ok(value),
+            // The compiler rejects `break` and `continue` outside of a loop.
+            Flow::Next | Flow::Break | Flow::Continue => Ok(Value::Void),
+        }
+    }
 }
 
 impl From<Expression> for Statement {
     fn from(expression: Expression) -> Statement {
-        Statement::FunctionCall(expression)
+        let span = expression.span;
+        Statement::new(StatementKind::FunctionCall(expression), span)
     }
 }
 
 impl Statement {
-    pub fn execute(&self, context: &mut ExecutionContext) -> Result<(), RuntimeError> {
-        match self {
-            Statement::FunctionCall(call) => {
-                call.evaluate(context)?;
-            }
-            Statement::Assignment(name, expression) => {
-                let value = expression.evaluate(context)?;
-                context.set_variable(name.clone(), value);
-            }
-            Statement::Conditional(condition, body, else_body) => {
-                match condition.evaluate(context)? {
-                    Value::Boolean(true) => body.execute(context)?,
-                    Value::Boolean(false) => {
-                        if let Some(else_body) = else_body {
-                            else_body.execute(context)?;
-                        }
-                    }
-                    value => {
-                        return Err(format!("Invalid condition return type: {:?}", value).into());
-                    }
-                }
-            }
-            Statement::Loop(condition, body) => {
-                while let Value::Boolean(true) = condition.evaluate(context)? {
-                    body.execute(context)?;
-                }
-            }
-            Statement::CodeBlock(statements) => {
-                for statement in statements {
-                    statement.execute(context)?;
-                }
-            }
-        }
-
-        Ok(())
+    pub fn new(kind: StatementKind, span: SourceSpan) -> Self {
+        Self {
+            kind,
+            span,
+            doc: None,
+        }
+    }
+
+    /// Executes the statement; errors not located yet get this statement's span.
+    pub fn execute(&self, context: &mut ExecutionContext) -> Result<Flow, RuntimeError> {
+        self.execute_kind(context)
+            .map_err(|error| error.at(self.span))
+    }
+
+    fn execute_kind(&self, context: &mut ExecutionContext) -> Result<Flow, RuntimeError> {
+        match &self.kind {
+            StatementKind::FunctionCall(call) => call.evaluate(context).map(|_| Flow::Next),
+            StatementKind::Declaration(name, expression) => {
+                Self::declare(name, expression, context)
+            }
+            StatementKind::Assignment(name, expression) => Self::assign(name, expression, context),
+            StatementKind::IndexAssignment(array, index, value) => {
+                Self::assign_index(array, index, value, context)
+            }
+            StatementKind::Conditional(condition, body, else_body) => {
+                Self::execute_if(condition, body, else_body.as_deref(), context)
+            }
+            StatementKind::Loop(condition, body) => Self::execute_while(condition, body, context),
+            StatementKind::For(name, start, end, body) => {
+                Self::execute_range(name, start, end, body, context)
+            }
+            StatementKind::ForEach(name, collection, body) => {
+                Self::execute_for_each(name, collection, body, context)
+            }
+            StatementKind::Break => Ok(Flow::Break),
+            StatementKind::Continue => Ok(Flow::Continue),
+            StatementKind::CodeBlock(statements) => Self::execute_block(statements, context),
+            StatementKind::FunctionDeclaration(function) => {
+                context.set_function(function.name.clone(), function.clone());
+
+                Ok(Flow::Next)
+            }
+            StatementKind::Return(Some(expression)) => {
+                expression.evaluate(context).map(Flow::Return)
+            }
+            StatementKind::Return(None) => Ok(Flow::Return(Value::Void)),
+        }
+    }
+
+    // As in `Expression::evaluate_kind`, arms with locals of their own live in separate
+    // functions to keep the frames of recursive calls small in debug builds.
+
+    fn declare(
+        name: &str,
+        expression: &Expression,
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        let value = expression.evaluate(context)?;
+        context.set_variable(name.to_string(), value);
+
+        Ok(Flow::Next)
+    }
+
+    fn assign(
+        name: &str,
+        expression: &Expression,
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        let value = expression.evaluate(context)?;
+
+        context.assign_variable(name, value)?;
+
+        Ok(Flow::Next)
+    }
+
+    fn assign_index(
+        array: &Expression,
+        index: &Expression,
+        value: &Expression,
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        let array = array.evaluate(context)?;
+        let index = index.evaluate(context)?;
+        let value = value.evaluate(context)?;
+
+        array.set_index(&index, value)?;
+
+        Ok(Flow::Next)
+    }
+
+    fn execute_if(
+        condition: &Expression,
+        body: &Statement,
+        else_body: Option<&Statement>,
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        if Self::evaluate_condition(condition, context)? {
+            body.execute(context)
+        } else if let Some(else_body) = else_body {
+            else_body.execute(context)
+        } else {
+            Ok(Flow::Next)
+        }
+    }
+
+    fn execute_block(
+        statements: &[Statement],
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        context.push_scope();
+        let flow = Self::execute_all(statements, context);
+        context.pop_scope();
+
+        flow
+    }
+
+    fn execute_while(
+        condition: &Expression,
+        body: &Statement,
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        while Self::evaluate_condition(condition, context)? {
+            match body.execute(context)? {
+                Flow::Break => break,
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Next | Flow::Continue => {}
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+
+    fn execute_range(
+        name: &str,
+        start: &Expression,
+        end: &Expression,
+        body: &Statement,
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        let start = start.evaluate(context)?;
+        let end = end.evaluate(context)?;
+        let (mut current, mut end) = range_bounds(start, end)?;
+        let values = std::iter::from_fn(|| range_step(&mut current, &mut end));
+
+        Self::execute_for(name, values, body, context)
+    }
+
+    fn execute_for_each(
+        name: &str,
+        collection: &Expression,
+        body: &Statement,
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        let values = collection.evaluate(context)?.iteration()?;
+
+        Self::execute_for(name, values.into_iter(), body, context)
+    }
+
+    /// Runs the body once per value, each time in a new scope binding it to `name`.
+    fn execute_for(
+        name: &str,
+        values: impl Iterator<Item = Value>,
+        body: &Statement,
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        for value in values {
+            context.push_scope();
+            context.set_variable(name.to_string(), value);
+            let flow = body.execute(context);
+            context.pop_scope();
+
+            match flow? {
+                Flow::Break => break,
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Next | Flow::Continue => {}
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+
+    fn evaluate_condition(
+        condition: &Expression,
+        context: &mut ExecutionContext,
+    ) -> Result<bool, RuntimeError> {
+        condition
+            .evaluate(context)?
+            .as_condition()
+            .map_err(|error| error.at(condition.span))
+    }
+
+    /// Executes statements in order, stopping at the first one that leaves early.
+    pub fn execute_all(
+        statements: &[Statement],
+        context: &mut ExecutionContext,
+    ) -> Result<Flow, RuntimeError> {
+        for statement in statements {
+            match statement.execute(context)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+}
+
+/// Checks the bounds of a `for` range and returns its state for [`range_step`], shared
+/// by both execution engines.
+///
+/// A range between two integers counts in integers and holds its next value and its
+/// end. Any float bound makes it count in floats; adding 1 to a float past 2^53 leaves
+/// it unchanged, so such a range holds its next value and the number of values left,
+/// counted up front.
+pub(crate) fn range_bounds(start: Value, end: Value) -> Result<(Value, Value), RuntimeError> {
+    match (&start, &end) {
+        (Value::Integer(_), Value::Integer(_)) => Ok((start, end)),
+        (Value::Numerical(_) | Value::Integer(_), Value::Numerical(_) | Value::Integer(_)) => {
+            let (start, end) = (f64::try_from(&start)?, f64::try_from(&end)?);
+            // Saturates, and gives no values for NaN bounds.
+            let count = (end - start).ceil().max(0.0) as i64;
+
+            Ok((start.into(), Value::Integer(count)))
+        }
+        _ => Err(format!(
+            "Range bounds must be numbers, got {:?} and {:?}",
+            start, end
+        )
+        .into()),
+    }
+}
+
+/// Advances the state of a range returned by [`range_bounds`] and returns its next
+/// value, or `None` once the range is exhausted.
+pub(crate) fn range_step(current: &mut Value, end: &mut Value) -> Option<Value> {
+    match (current, end) {
+        (Value::Integer(current), Value::Integer(end)) if *current < *end => {
+            let value = *current;
+            *current += 1;
+
+            Some(Value::Integer(value))
+        }
+        (Value::Numerical(current), Value::Integer(remaining)) if *remaining > 0 => {
+            let value = *current;
+            *current += 1.0;
+            *remaining -= 1;
+
+            Some(Value::Numerical(value))
+        }
+        _ => None,
     }
 }
@@ -0,0 +1,367 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::compiler::CompilationError;
+use crate::expression::{Expression, ExpressionKind, Function};
+use crate::runtime::Arity;
+use crate::span::SourceSpan;
+use crate::statement::{Statement, StatementKind, UserFunction};
+use crate::value::Type;
+
+/// Static pass run over a compiled program before it may execute.
+///
+/// Variable types are inferred from their `let` initializers. Names the checker cannot
+/// resolve, such as function parameters, variables set by the embedder and results of
+/// user or host functions, are typed `Any` and left for the runtime to check.
+pub struct TypeChecker {
+    scopes: Vec<HashMap<String, Type>>,
+    /// Arity of every `fn` in the program, `None` if a name is declared with several.
+    functions: HashMap<String, Option<usize>>,
+    /// User and host functions defined before the program runs, which may replace
+    /// builtins and are only checked at runtime.
+    defined_functions: HashSet<String>,
+    errors: Vec<CompilationError>,
+}
+
+impl TypeChecker {
+    pub fn check(
+        statements: &[Statement],
+        result: Option<&Expression>,
+        defined_functions: HashSet<String>,
+    ) -> Result<(), Vec<CompilationError>> {
+        let mut checker = Self {
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            defined_functions,
+            errors: Vec::new(),
+        };
+
+        checker.collect_functions(statements);
+        checker.check_statements(statements);
+
+        if let Some(result) = result {
+            checker.type_of(result);
+        }
+
+        if checker.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(checker.errors)
+        }
+    }
+
+    fn collect_functions(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match &statement.kind {
+                StatementKind::FunctionDeclaration(function) => {
+                    let arity = Some(function.parameters.len());
+
+                    self.functions
+                        .entry(function.name.clone())
+                        .and_modify(|existing| {
+                            if *existing != arity {
+                                *existing = None;
+                            }
+                        })
+                        .or_insert(arity);
+
+                    self.collect_functions(&function.body);
+                }
+                StatementKind::Conditional(_, body, else_body) => {
+                    self.collect_functions(std::slice::from_ref(body));
+
+                    if let Some(else_body) = else_body {
+                        self.collect_functions(std::slice::from_ref(else_body));
+                    }
+                }
+                StatementKind::Loop(_, body)
+                | StatementKind::For(_, _, _, body)
+                | StatementKind::ForEach(_, _, body) => {
+                    self.collect_functions(std::slice::from_ref(body))
+                }
+                StatementKind::CodeBlock(statements) => self.collect_functions(statements),
+                _ => {}
+            }
+        }
+    }
+
+    fn check_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::FunctionCall(call) => {
+                self.type_of(call);
+            }
+            StatementKind::Declaration(name, expression) => {
+                let value_type = self.type_of(expression);
+
+                self.scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert(name.clone(), value_type);
+            }
+            StatementKind::Assignment(name, expression) => {
+                let value_type = self.type_of(expression);
+
+                if let Some(variable_type) = self.lookup(name) {
+                    if !variable_type.accepts(value_type) {
+                        self.error(
+                            expression.span,
+                            format!(
+                                "Cannot assign {} to variable `{}` of type {}",
+                                value_type, name, variable_type
+                            ),
+                        );
+                    }
+                }
+            }
+            StatementKind::Conditional(condition, body, else_body) => {
+                self.expect_condition(condition, "if");
+                self.check_statement(body);
+
+                if let Some(else_body) = else_body {
+                    self.check_statement(else_body);
+                }
+            }
+            StatementKind::Loop(condition, body) => {
+                self.expect_condition(condition, "while");
+                self.check_statement(body);
+            }
+            StatementKind::For(name, start, end, body) => {
+                for bound in [start, end] {
+                    let bound_type = self.type_of(bound);
+
+                    if !Type::Number.accepts(bound_type) {
+                        self.error(
+                            bound.span,
+                            format!("Range bounds must be numbers, found {}", bound_type),
+                        );
+                    }
+                }
+
+                self.scopes
+                    .push(HashMap::from([(name.clone(), Type::Number)]));
+                self.check_statement(body);
+                self.scopes.pop();
+            }
+            StatementKind::ForEach(name, collection, body) => {
+                let collection_type = self.type_of(collection);
+
+                if !Type::Array.accepts(collection_type) && !Type::Map.accepts(collection_type) {
+                    self.error(
+                        collection.span,
+                        format!("Cannot iterate over {}", collection_type),
+                    );
+                }
+
+                self.scopes.push(HashMap::from([(name.clone(), Type::Any)]));
+                self.check_statement(body);
+                self.scopes.pop();
+            }
+            StatementKind::IndexAssignment(array, index, value) => {
+                self.check_index(array, index);
+                self.type_of(value);
+            }
+            StatementKind::Break | StatementKind::Continue => {}
+            StatementKind::CodeBlock(statements) => {
+                self.scopes.push(HashMap::new());
+                self.check_statements(statements);
+                self.scopes.pop();
+            }
+            StatementKind::FunctionDeclaration(function) => self.check_function(function),
+            StatementKind::Return(expression) => {
+                if let Some(expression) = expression {
+                    self.type_of(expression);
+                }
+            }
+        }
+    }
+
+    /// Checks a function body in isolation: only its parameters are in scope.
+    fn check_function(&mut self, function: &UserFunction) {
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| (parameter.clone(), Type::Any))
+            .collect();
+
+        let outer = std::mem::replace(&mut self.scopes, vec![parameters]);
+        self.check_statements(&function.body);
+        self.scopes = outer;
+    }
+
+    fn expect_condition(&mut self, condition: &Expression, keyword: &str) {
+        let condition_type = self.type_of(condition);
+
+        if !Type::Boolean.accepts(condition_type) {
+            self.error(
+                condition.span,
+                format!(
+                    "`{}` condition must be boolean, found {}",
+                    keyword, condition_type
+                ),
+            );
+        }
+    }
+
+    fn type_of(&mut self, expression: &Expression) -> Type {
+        match &expression.kind {
+            ExpressionKind::Number(_) | ExpressionKind::Integer(_) => Type::Number,
+            ExpressionKind::Boolean(_) => Type::Boolean,
+            ExpressionKind::Str(_) => Type::Str,
+            ExpressionKind::BracketExpression(inner) => self.type_of(inner),
+            ExpressionKind::Array(elements) => {
+                for element in elements {
+                    self.type_of(element);
+                }
+
+                Type::Array
+            }
+            ExpressionKind::Map(entries) => {
+                for (_, value) in entries {
+                    self.type_of(value);
+                }
+
+                Type::Map
+            }
+            ExpressionKind::Index(array, index) => {
+                self.check_index(array, index);
+                Type::Any
+            }
+            ExpressionKind::Unary(operator, operand) => {
+                let operand = self.type_of(operand);
+
+                operator.result_type(operand).unwrap_or_else(|| {
+                    self.error(
+                        expression.span,
+                        format!(
+                            "Invalid type for unary operator `{:?}`: {}",
+                            operator, operand
+                        ),
+                    );
+                    Type::Any
+                })
+            }
+            ExpressionKind::BinaryExpression(left, operator, right) => {
+                let left = self.type_of(left);
+                let right = self.type_of(right);
+
+                operator.result_type(left, right).unwrap_or_else(|| {
+                    self.error(
+                        expression.span,
+                        format!(
+                            "Invalid types for binary operator `{:?}`: {} and {}",
+                            operator, left, right
+                        ),
+                    );
+                    Type::Any
+                })
+            }
+            ExpressionKind::Function(name, arguments) => {
+                let arguments: Vec<Type> = arguments
+                    .iter()
+                    .map(|argument| self.type_of(argument))
+                    .collect();
+
+                self.call_type(name, &arguments, expression.span)
+            }
+            ExpressionKind::Variable(name) => self.lookup(name).unwrap_or(Type::Any),
+        }
+    }
+
+    /// Checks `collection[index]`: arrays take number indices and maps string keys.
+    fn check_index(&mut self, collection: &Expression, index: &Expression) {
+        let collection_type = self.type_of(collection);
+        let index_type = self.type_of(index);
+
+        let (expected, description) = match collection_type {
+            Type::Array => (Type::Number, "Array index must be a number"),
+            Type::Map => (Type::Str, "Map key must be a string"),
+            Type::Any => return,
+            other => {
+                self.error(collection.span, format!("Cannot index into {}", other));
+                return;
+            }
+        };
+
+        if !expected.accepts(index_type) {
+            self.error(index.span, format!("{}, found {}", description, index_type));
+        }
+    }
+
+    fn call_type(&mut self, name: &str, arguments: &[Type], span: SourceSpan) -> Type {
+        if let Some(arity) = self.functions.get(name) {
+            if let Some(arity) = arity.filter(|arity| *arity != arguments.len()) {
+                self.arity_error(name, Arity::Exact(arity), arguments.len(), span);
+            }
+
+            return Type::Any;
+        }
+
+        if self.defined_functions.contains(name) {
+            return Type::Any;
+        }
+
+        // Anything else may be a host function registered at runtime.
+        let Ok(function) = Function::from_str(name) else {
+            return Type::Any;
+        };
+
+        if !function.arity().accepts(arguments.len()) {
+            self.arity_error(name, function.arity(), arguments.len(), span);
+        }
+
+        if let Some(parameters) = function.parameters() {
+            // Variadic functions check their extra arguments against the last parameter.
+            let rest = match function.arity() {
+                Arity::AtLeast(_) => parameters.last(),
+                _ => None,
+            };
+
+            for (index, found) in arguments.iter().enumerate() {
+                let Some(expected) = parameters.get(index).or(rest) else {
+                    break;
+                };
+
+                if !expected.accepts(*found) {
+                    self.error(
+                        span,
+                        format!(
+                            "Argument {} of `{}` must be {}, found {}",
+                            index + 1,
+                            name,
+                            expected,
+                            found
+                        ),
+                    );
+                }
+            }
+        }
+
+        function.return_type()
+    }
+
+    fn lookup(&self, name: &str) -> Option<Type> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .copied()
+    }
+
+    fn arity_error(&mut self, name: &str, expected: Arity, found: usize, span: SourceSpan) {
+        self.error(
+            span,
+            format!("Function `{}` expects {}, got {}", name, expected, found),
+        );
+    }
+
+    fn error(&mut self, span: SourceSpan, context: String) {
+        self.errors
+            .push(CompilationError::Type { pos: span, context });
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{
+    runtime::RuntimeError,
+    value::{Type, Value},
+};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Negation,
+    Not,
+}
+
+impl UnaryOperator {
+    pub fn apply(&self, operand: Value) -> Result<Value, RuntimeError> {
+        match (self, &operand) {
+            (UnaryOperator::Negation, Value::Numerical(number)) => Ok((-number).into()),
+            (UnaryOperator::Negation, Value::Integer(integer)) => integer
+                .checked_neg()
+                .map(Value::Integer)
+                .ok_or_else(|| format!("Integer overflow in `{:?}`: {}", self, integer).into()),
+            (UnaryOperator::Not, Value::Boolean(boolean)) => Ok((!boolean).into()),
+            _ => Err(format!(
+                "Invalid type for unary operator `{:?}`: {:?}",
+                self, operand
+            )
+            .into()),
+        }
+    }
+
+    /// Static counterpart of [`UnaryOperator::apply`]: the result type for the given
+    /// operand type, or `None` if no value of that type is accepted.
+    pub fn result_type(&self, operand: Type) -> Option<Type> {
+        let accepted = match self {
+            UnaryOperator::Negation => Type::Number,
+            UnaryOperator::Not => Type::Boolean,
+        };
+
+        accepted.accepts(operand).then_some(accepted)
+    }
+}
+
+impl FromStr for UnaryOperator {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "-" => Ok(UnaryOperator::Negation),
+            "!" => Ok(UnaryOperator::Not),
+            _ => Err(format!("Unknown UnaryOperator: {}", value)),
+        }
+    }
+}
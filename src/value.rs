@@ -1,8 +1,70 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::runtime::RuntimeError;
+
+/// A runtime value.
+///
+/// `PartialEq` compares like kinds field by field, so `Integer(2) != Numerical(2.0)`;
+/// the language's own `==` is [`Value::structural_eq`].
+#[derive(Clone)]
 pub enum Value {
     Numerical(f64),
+    /// A whole number from an integer literal, a count or integer arithmetic.
+    Integer(i64),
     Boolean(bool),
+    Str(Rc<str>),
+    /// A list shared by reference: copies of the value see each other's updates.
+    Array(Rc<RefCell<Vec<Value>>>),
+    /// A record with string keys, shared by reference like arrays. Keys are kept
+    /// sorted, which is the order they are printed and iterated in.
+    Map(Rc<RefCell<BTreeMap<String, Value>>>),
+    /// A user, host or builtin function referred to by name.
+    Function(Rc<str>),
+    Void,
+}
+
+/// Static type of a value, as inferred by the type checker.
+///
+/// `Any` stands for values whose type is only known at runtime, such as
+/// function parameters or results of host functions. `Number` covers both integers
+/// and floats, which mix freely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Number,
+    Boolean,
+    Str,
+    Array,
+    Map,
+    Function,
     Void,
+    Any,
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Type::Number => "number",
+            Type::Boolean => "boolean",
+            Type::Str => "string",
+            Type::Array => "array",
+            Type::Map => "map",
+            Type::Function => "function",
+            Type::Void => "void",
+            Type::Any => "any",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Type {
+    /// Whether a value of type `other` may be used where `self` is expected.
+    pub fn accepts(&self, other: Type) -> bool {
+        *self == Type::Any || other == Type::Any || *self == other
+    }
 }
 
 impl From<f64> for Value {
@@ -11,8 +73,460 @@ impl From<f64> for Value {
     }
 }
 
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<usize> for Value {
+    /// Converts a count or position, which always fits for in-memory collections.
+    fn from(value: usize) -> Self {
+        Value::Integer(value as i64)
+    }
+}
+
 impl From<bool> for Value {
     fn from(value: bool) -> Self {
         Value::Boolean(value)
     }
 }
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Str(value.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value.into())
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(values: Vec<Value>) -> Self {
+        Value::Array(Rc::new(RefCell::new(values)))
+    }
+}
+
+impl From<BTreeMap<String, Value>> for Value {
+    fn from(entries: BTreeMap<String, Value>) -> Self {
+        Value::Map(Rc::new(RefCell::new(entries)))
+    }
+}
+
+impl FromIterator<(String, Value)> for Value {
+    /// Collects key-value pairs into a map; later duplicates of a key win.
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(entries: I) -> Self {
+        entries.into_iter().collect::<BTreeMap<_, _>>().into()
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::Void
+    }
+}
+
+impl TryFrom<&Value> for f64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Numerical(number) => Ok(*number),
+            Value::Integer(integer) => Ok(*integer as f64),
+            other => Err(format!("Expected a number, got {:?}", other).into()),
+        }
+    }
+}
+
+impl TryFrom<&Value> for bool {
+    type Error = RuntimeError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Boolean(boolean) => Ok(*boolean),
+            other => Err(format!("Expected a boolean, got {:?}", other).into()),
+        }
+    }
+}
+
+impl TryFrom<&Value> for String {
+    type Error = RuntimeError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Str(string) => Ok(string.to_string()),
+            other => Err(format!("Expected a string, got {:?}", other).into()),
+        }
+    }
+}
+
+impl Value {
+    pub fn type_of(&self) -> Type {
+        match self {
+            Value::Numerical(_) | Value::Integer(_) => Type::Number,
+            Value::Boolean(_) => Type::Boolean,
+            Value::Str(_) => Type::Str,
+            Value::Array(_) => Type::Array,
+            Value::Map(_) => Type::Map,
+            Value::Function(_) => Type::Function,
+            Value::Void => Type::Void,
+        }
+    }
+
+    /// Reads `self[index]`, an element of an array or the value of a map key.
+    pub(crate) fn index(&self, index: &Value) -> Result<Value, RuntimeError> {
+        match self {
+            Value::Array(elements) => {
+                let elements = elements.borrow();
+                let index = array_index(index, elements.len())?;
+
+                Ok(elements[index].clone())
+            }
+            Value::Map(entries) => {
+                let key = map_key(index)?;
+
+                entries
+                    .borrow()
+                    .get(key)
+                    .cloned()
+                    .ok_or_else(|| format!("Key {:?} not found in map", key).into())
+            }
+            value => Err(format!("Cannot index into {}", value.type_of()).into()),
+        }
+    }
+
+    /// Writes `self[index]`, visible through every copy of the collection. Assigning
+    /// to a missing map key adds it.
+    pub(crate) fn set_index(&self, index: &Value, value: Value) -> Result<(), RuntimeError> {
+        match self {
+            Value::Array(elements) => {
+                let mut elements = elements.borrow_mut();
+                let index = array_index(index, elements.len())?;
+
+                elements[index] = value;
+            }
+            Value::Map(entries) => {
+                let key = map_key(index)?;
+                entries.borrow_mut().insert(key.to_string(), value);
+            }
+            value => return Err(format!("Cannot index into {}", value.type_of()).into()),
+        }
+
+        Ok(())
+    }
+
+    /// Values a `for` loop visits: the elements of an array or the keys of a map, as
+    /// they are when the loop starts.
+    pub(crate) fn iteration(&self) -> Result<Vec<Value>, RuntimeError> {
+        match self {
+            Value::Array(elements) => Ok(elements.borrow().clone()),
+            Value::Map(entries) => Ok(entries
+                .borrow()
+                .keys()
+                .map(|key| Value::from(key.as_str()))
+                .collect()),
+            value => Err(format!("Cannot iterate over {}", value.type_of()).into()),
+        }
+    }
+
+    /// The value as a float, for integers and floats alike.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Numerical(number) => Some(*number),
+            Value::Integer(integer) => Some(*integer as f64),
+            _ => None,
+        }
+    }
+
+    /// Orders two numbers exactly, even an integer past 2^53 against a float; `None`
+    /// if either is NaN or not a number.
+    pub(crate) fn compare_numbers(&self, other: &Value) -> Option<Ordering> {
+        use Value::*;
+
+        match (self, other) {
+            (Integer(left), Integer(right)) => Some(left.cmp(right)),
+            (Numerical(left), Numerical(right)) => left.partial_cmp(right),
+            (Integer(integer), Numerical(number)) => integer_cmp(*integer, *number),
+            (Numerical(number), Integer(integer)) => {
+                integer_cmp(*integer, *number).map(Ordering::reverse)
+            }
+            _ => None,
+        }
+    }
+
+    /// Interprets the value as an `if`/`while` condition.
+    pub(crate) fn as_condition(&self) -> Result<bool, RuntimeError> {
+        match self {
+            Value::Boolean(boolean) => Ok(*boolean),
+            value => Err(format!("Invalid condition return type: {:?}", value).into()),
+        }
+    }
+
+    /// Structural equality used by the `==` and `!=` operators.
+    ///
+    /// Values of different kinds are never equal, but integers and floats compare by
+    /// their exact numeric value. Numbers follow IEEE 754: `NaN` is not equal to
+    /// anything, itself included, and `0.0 == -0.0`.
+    pub fn structural_eq(&self, other: &Value) -> bool {
+        self.eq_by(other, &mut Vec::new(), |left, right| match (left, right) {
+            (Value::Integer(integer), Value::Numerical(number))
+            | (Value::Numerical(number), Value::Integer(integer)) => integer_eq(*integer, *number),
+            (left, right) => left.scalar_eq(right),
+        })
+    }
+
+    /// Compares arrays and maps element by element and everything else with `leaf`.
+    ///
+    /// A collection can contain itself, so `comparing` holds the pairs of collections
+    /// compared further up. Meeting such a pair again adds nothing new: it counts as
+    /// equal and the remaining elements decide.
+    fn eq_by(
+        &self,
+        other: &Value,
+        comparing: &mut Vec<(usize, usize)>,
+        leaf: fn(&Value, &Value) -> bool,
+    ) -> bool {
+        let (Some(left_id), Some(right_id)) = (self.collection_id(), other.collection_id()) else {
+            return leaf(self, other);
+        };
+
+        if comparing.contains(&(left_id, right_id)) {
+            return true;
+        }
+
+        comparing.push((left_id, right_id));
+
+        let equal = match (self, other) {
+            (Value::Array(left), Value::Array(right)) => {
+                let (left, right) = (left.borrow(), right.borrow());
+
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right.iter())
+                        .all(|(left, right)| left.eq_by(right, comparing, leaf))
+            }
+            (Value::Map(left), Value::Map(right)) => {
+                let (left, right) = (left.borrow(), right.borrow());
+
+                left.len() == right.len()
+                    && left.iter().zip(right.iter()).all(|(left, right)| {
+                        left.0 == right.0 && left.1.eq_by(right.1, comparing, leaf)
+                    })
+            }
+            _ => false,
+        };
+
+        comparing.pop();
+        equal
+    }
+
+    /// Equality of two values of the same kind that are not collections.
+    fn scalar_eq(&self, other: &Value) -> bool {
+        use Value::*;
+
+        match (self, other) {
+            (Numerical(left), Numerical(right)) => left == right,
+            (Integer(left), Integer(right)) => left == right,
+            (Boolean(left), Boolean(right)) => left == right,
+            (Str(left), Str(right)) => left == right,
+            (Function(left), Function(right)) => left == right,
+            (Void, Void) => true,
+            _ => false,
+        }
+    }
+
+    /// Identity of an array or map, shared by all copies of the value.
+    fn collection_id(&self) -> Option<usize> {
+        match self {
+            Value::Array(elements) => Some(Rc::as_ptr(elements) as *const () as usize),
+            Value::Map(entries) => Some(Rc::as_ptr(entries) as *const () as usize),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        self.eq_by(other, &mut Vec::new(), Value::scalar_eq)
+    }
+}
+
+/// Whether `number` is exactly `integer`, without the rounding of `integer as f64`
+/// past 2^53.
+fn integer_eq(integer: i64, number: f64) -> bool {
+    integer_cmp(integer, number) == Some(Ordering::Equal)
+}
+
+/// Orders `integer` against `number` without rounding either.
+fn integer_cmp(integer: i64, number: f64) -> Option<Ordering> {
+    if number.is_nan() {
+        return None;
+    }
+
+    // Every whole float in the range of `i128` converts exactly, and `as` saturates
+    // the rest, including the infinities, beyond any `i64`.
+    let floor = number.floor();
+    let fraction = if number > floor {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    };
+
+    Some((integer as i128).cmp(&(floor as i128)).then(fraction))
+}
+
+pub(crate) fn map_key(key: &Value) -> Result<&str, RuntimeError> {
+    match key {
+        Value::Str(key) => Ok(key),
+        key => Err(format!("Map key must be a string, got {}", key.type_of()).into()),
+    }
+}
+
+/// Checks that `index` is a whole number addressing one of `length` elements.
+pub(crate) fn array_index(index: &Value, length: usize) -> Result<usize, RuntimeError> {
+    match index {
+        Value::Integer(integer) => usize::try_from(*integer)
+            .ok()
+            .filter(|index| *index < length)
+            .ok_or_else(|| {
+                format!(
+                    "Index {} out of bounds for array of length {}",
+                    integer, length
+                )
+                .into()
+            }),
+        Value::Numerical(number) if number.fract() == 0.0 => {
+            if *number >= 0.0 && *number < length as f64 {
+                Ok(*number as usize)
+            } else {
+                Err(format!(
+                    "Index {} out of bounds for array of length {}",
+                    number, length
+                )
+                .into())
+            }
+        }
+        Value::Numerical(number) => {
+            Err(format!("Array index must be a whole number, got {}", number).into())
+        }
+        value => Err(format!("Array index must be a number, got {}", value.type_of()).into()),
+    }
+}
+
+impl std::fmt::Display for Value {
+    /// Formats the value as `print` writes it; strings inside collections are quoted.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.write(f, &mut Vec::new(), false)
+    }
+}
+
+impl std::fmt::Debug for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.write(f, &mut Vec::new(), true)
+    }
+}
+
+impl Value {
+    /// Writes the value for `Display`, or for `Debug` with the kind of every value.
+    ///
+    /// `open` holds the collections being written further up, so that a collection
+    /// containing itself is written as `[...]` or `{...}` where it recurs.
+    fn write(
+        &self,
+        f: &mut std::fmt::Formatter,
+        open: &mut Vec<usize>,
+        debug: bool,
+    ) -> std::fmt::Result {
+        if debug {
+            match self {
+                Value::Numerical(number) => return write!(f, "Numerical({:?})", number),
+                Value::Integer(integer) => return write!(f, "Integer({})", integer),
+                Value::Boolean(boolean) => return write!(f, "Boolean({})", boolean),
+                Value::Str(string) => return write!(f, "Str({:?})", string),
+                Value::Array(_) => write!(f, "Array(")?,
+                Value::Map(_) => write!(f, "Map(")?,
+                Value::Function(name) => return write!(f, "Function({:?})", name),
+                Value::Void => return write!(f, "Void"),
+            }
+        }
+
+        match self {
+            Value::Numerical(number) => write!(f, "{}", number)?,
+            Value::Integer(integer) => write!(f, "{}", integer)?,
+            Value::Boolean(boolean) => write!(f, "{}", boolean)?,
+            Value::Str(string) => write!(f, "{}", string)?,
+            Value::Array(_) if open.contains(&self.collection_id().unwrap()) => write!(f, "[...]")?,
+            Value::Map(_) if open.contains(&self.collection_id().unwrap()) => write!(f, "{{...}}")?,
+            Value::Array(elements) => {
+                open.push(self.collection_id().unwrap());
+                write!(f, "[")?;
+
+                for (position, element) in elements.borrow().iter().enumerate() {
+                    if position > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    element.write_element(f, open, debug)?;
+                }
+
+                write!(f, "]")?;
+                open.pop();
+            }
+            Value::Map(entries) => {
+                open.push(self.collection_id().unwrap());
+                write!(f, "{{")?;
+
+                for (position, (key, value)) in entries.borrow().iter().enumerate() {
+                    if position > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    if is_identifier(key) && !debug {
+                        write!(f, "{}: ", key)?;
+                    } else {
+                        write!(f, "{:?}: ", key)?;
+                    }
+
+                    value.write_element(f, open, debug)?;
+                }
+
+                write!(f, "}}")?;
+                open.pop();
+            }
+            Value::Function(name) => write!(f, "<fn {}>", name)?,
+            Value::Void => {}
+        }
+
+        if debug {
+            write!(f, ")")?;
+        }
+
+        Ok(())
+    }
+
+    fn write_element(
+        &self,
+        f: &mut std::fmt::Formatter,
+        open: &mut Vec<usize>,
+        debug: bool,
+    ) -> std::fmt::Result {
+        match self {
+            Value::Str(string) if !debug => write!(f, "{:?}", string),
+            element => element.write(f, open, debug),
+        }
+    }
+}
+
+/// Whether a map key can be written without quotes, as in a map literal.
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic())
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
@@ -0,0 +1,346 @@
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use crate::bytecode::{BytecodeProgram, Chunk, FunctionChunk, Instruction};
+use crate::expression::{call_native, is_native, Caller};
+use crate::math;
+use crate::runtime::{Arity, ExecutionContext, RuntimeError, MAX_VM_CALL_DEPTH};
+use crate::statement::{range_bounds, range_step};
+use crate::value::{map_key, Value};
+
+struct CallFrame {
+    function: Rc<FunctionChunk>,
+    ip: usize,
+    /// Stack index of the frame's first local slot.
+    base: usize,
+}
+
+/// Stack-based virtual machine running a [`BytecodeProgram`].
+///
+/// Locals and temporaries share one value stack and calls push frames instead of
+/// recursing, so user function calls do not consume native stack.
+struct Vm<'a> {
+    program: &'a BytecodeProgram,
+    context: &'a mut ExecutionContext,
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    globals: Vec<Option<Value>>,
+    functions: HashMap<Rc<str>, Rc<FunctionChunk>>,
+}
+
+impl BytecodeProgram {
+    /// Runs the program on the VM.
+    ///
+    /// Globals already set in `context` are visible to the program, and the program's
+    /// globals are written back into `context` once it finishes, as after
+    /// [`crate::compiler::Program::execute`].
+    pub fn execute(&self, context: &mut ExecutionContext) -> Result<(), RuntimeError> {
+        let globals = self
+            .globals
+            .iter()
+            .map(|name| context.get_variable(&name.to_string()).cloned())
+            .collect();
+
+        let mut vm = Vm {
+            program: self,
+            context,
+            stack: vec![Value::Void; self.main.chunk.slots],
+            frames: vec![CallFrame {
+                function: self.main.clone(),
+                ip: 0,
+                base: 0,
+            }],
+            globals,
+            functions: HashMap::new(),
+        };
+
+        let result = vm.run(0).map(|_| ()).map_err(|error| vm.locate(error));
+        let globals = std::mem::take(&mut vm.globals);
+
+        for (name, value) in self.globals.iter().zip(globals) {
+            if let Some(value) = value {
+                context.set_variable(name.to_string(), value);
+            }
+        }
+
+        result
+    }
+}
+
+impl<'a> Vm<'a> {
+    /// Runs until a return leaves `depth` frames on the call stack and yields the
+    /// returned value.
+    fn run(&mut self, depth: usize) -> Result<Value, RuntimeError> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let instruction = frame.function.chunk.code[frame.ip];
+            let base = frame.base;
+            frame.ip += 1;
+
+            match instruction {
+                Instruction::Constant(index) => {
+                    let value = self.chunk().constants[index as usize].clone();
+                    self.stack.push(value);
+                }
+                Instruction::GetLocal(slot) => {
+                    let value = self.stack[base + slot as usize].clone();
+                    self.stack.push(value);
+                }
+                Instruction::SetLocal(slot) => {
+                    let value = self.pop();
+                    self.stack[base + slot as usize] = value;
+                }
+                Instruction::GetGlobal(slot) => match &self.globals[slot as usize] {
+                    Some(value) => self.stack.push(value.clone()),
+                    None => {
+                        let name = &self.program.globals[slot as usize];
+                        return Err(format!("Unknown variable: {}", name).into());
+                    }
+                },
+                Instruction::DefineGlobal(slot) => {
+                    self.globals[slot as usize] = Some(self.pop());
+                }
+                Instruction::SetGlobal(slot) => {
+                    let value = self.pop();
+
+                    match &mut self.globals[slot as usize] {
+                        Some(global) => *global = value,
+                        None => {
+                            let name = &self.program.globals[slot as usize];
+                            return Err(
+                                format!("Assignment to undeclared variable: {}", name).into()
+                            );
+                        }
+                    }
+                }
+                Instruction::GetName(index) => {
+                    let name = self.chunk().names[index as usize].clone();
+
+                    match self.context.get_variable(&name.to_string()) {
+                        Some(value) => self.stack.push(value.clone()),
+                        None if self.functions.contains_key(&name)
+                            || is_native(&name, self.context) =>
+                        {
+                            self.stack.push(Value::Function(name))
+                        }
+                        None => match math::constant(&name) {
+                            Some(value) => self.stack.push(value),
+                            None => return Err(format!("Unknown variable: {}", name).into()),
+                        },
+                    }
+                }
+                Instruction::SetName(index) => {
+                    let name = self.chunk().names[index as usize].clone();
+                    let value = self.pop();
+
+                    self.context.assign_variable(&name, value)?;
+                }
+                Instruction::Unary(operator) => {
+                    let operand = self.pop();
+
+                    self.stack.push(operator.apply(operand)?);
+                }
+                Instruction::Binary(operator) => {
+                    let right = self.pop();
+                    let left = self.pop();
+
+                    self.stack.push(operator.apply(left, right)?);
+                }
+                Instruction::Array(count) => {
+                    let elements = self.stack.split_off(self.stack.len() - count as usize);
+                    self.stack.push(elements.into());
+                }
+                Instruction::Map(count) => {
+                    let entries = self.stack.split_off(self.stack.len() - 2 * count as usize);
+                    let mut map = BTreeMap::new();
+
+                    for entry in entries.chunks(2) {
+                        map.insert(map_key(&entry[0])?.to_string(), entry[1].clone());
+                    }
+
+                    self.stack.push(map.into());
+                }
+                Instruction::GetIndex => {
+                    let index = self.pop();
+                    let array = self.pop();
+
+                    self.stack.push(array.index(&index)?);
+                }
+                Instruction::SetIndex => {
+                    let value = self.pop();
+                    let index = self.pop();
+                    let array = self.pop();
+
+                    array.set_index(&index, value)?;
+                }
+                Instruction::Jump(target) => self.jump(target),
+                Instruction::ForRange(slot) => {
+                    let end = self.pop();
+                    let start = self.pop();
+                    let (start, end) = range_bounds(start, end)?;
+                    let slot = base + slot as usize;
+
+                    self.stack[slot] = start;
+                    self.stack[slot + 1] = end;
+                }
+                Instruction::ForEach(slot) => {
+                    let values = self.pop().iteration()?;
+                    let slot = base + slot as usize;
+
+                    self.stack[slot] = Value::Integer(0);
+                    self.stack[slot + 1] = values.into();
+                }
+                Instruction::ForNext { slot, exit } => {
+                    let slot = base + slot as usize;
+
+                    let [first, second] = &mut self.stack[slot..slot + 2] else {
+                        unreachable!("loops have two hidden slots");
+                    };
+
+                    // Collections hold their position and values, ranges the state
+                    // returned by `range_bounds`.
+                    let next = match (first, second) {
+                        (Value::Integer(position), Value::Array(values)) => {
+                            let value = values.borrow().get(*position as usize).cloned();
+                            *position += 1;
+
+                            value
+                        }
+                        (current, end) => range_step(current, end),
+                    };
+
+                    match next {
+                        Some(value) => self.stack.push(value),
+                        None => self.jump(exit),
+                    }
+                }
+                Instruction::ShortCircuit { result, exit } => {
+                    if self.stack.last() == Some(&Value::Boolean(result)) {
+                        self.jump(exit);
+                    }
+                }
+                Instruction::JumpIfFalse(target) => {
+                    if !self.pop().as_condition()? {
+                        self.jump(target);
+                    }
+                }
+                Instruction::Call { name, arguments } => {
+                    let name = self.chunk().names[name as usize].clone();
+                    self.call_function(&name, arguments as usize)?;
+                }
+                Instruction::DeclareFunction(index) => {
+                    let function = self.program.functions[index as usize].clone();
+                    self.functions.insert(function.name.clone(), function);
+                }
+                Instruction::Pop => {
+                    self.pop();
+                }
+                Instruction::Return => {
+                    let value = self.pop();
+                    let frame = self.frames.pop().unwrap();
+                    self.stack.truncate(frame.base);
+
+                    if self.frames.len() == depth {
+                        return Ok(value);
+                    }
+
+                    self.stack.push(value);
+                }
+            }
+        }
+    }
+
+    /// Calls `name` with the topmost `arguments` values: natives run at once and push
+    /// their result, user functions get a new frame.
+    fn call_function(&mut self, name: &str, arguments: usize) -> Result<(), RuntimeError> {
+        let Some(function) = self.functions.get(name).cloned() else {
+            let arguments = self.stack.split_off(self.stack.len() - arguments);
+            let value = call_native(name, &arguments, self)?;
+
+            self.stack.push(value);
+            return Ok(());
+        };
+
+        if arguments != function.arity {
+            return Err(format!(
+                "Function `{}` expects {}, got {}",
+                function.name,
+                Arity::Exact(function.arity),
+                arguments
+            )
+            .into());
+        }
+
+        let limit = self.context.max_call_depth(MAX_VM_CALL_DEPTH);
+
+        if self.frames.len() > limit {
+            return Err(format!("Maximum call depth of {} exceeded", limit).into());
+        }
+
+        let base = self.stack.len() - arguments;
+        self.stack.resize(base + function.chunk.slots, Value::Void);
+
+        self.frames.push(CallFrame {
+            function,
+            ip: 0,
+            base,
+        });
+
+        Ok(())
+    }
+
+    /// Locates an error at the instruction that raised it and records the active user
+    /// function calls, mirroring the tree-walker.
+    fn locate(&self, mut error: RuntimeError) -> RuntimeError {
+        let span_of = |frame: &CallFrame| frame.function.chunk.spans[frame.ip - 1];
+
+        if let Some(span) = self.frames.last().and_then(span_of) {
+            error = error.at(span);
+        }
+
+        let callees = self.frames.iter().rev();
+        let callers = self.frames.iter().rev().skip(1);
+
+        for (callee, caller) in callees.zip(callers) {
+            if let Some(call_site) = span_of(caller) {
+                error = error.called_from(&callee.function.name, call_site);
+            }
+        }
+
+        error
+    }
+
+    fn chunk(&self) -> &Chunk {
+        &self.frames.last().unwrap().function.chunk
+    }
+
+    fn jump(&mut self, target: u32) {
+        self.frames.last_mut().unwrap().ip = target as usize;
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("VM stack underflow")
+    }
+}
+
+impl Caller for Vm<'_> {
+    fn context(&mut self) -> &mut ExecutionContext {
+        self.context
+    }
+
+    /// Runs a callback to completion in a nested run loop, leaving the frames in place
+    /// if it fails so that the error is located like any other.
+    fn call(&mut self, name: &str, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let depth = self.frames.len();
+        let count = arguments.len();
+
+        self.stack.extend(arguments);
+        self.call_function(name, count)?;
+
+        if self.frames.len() == depth {
+            return Ok(self.pop());
+        }
+
+        self.run(depth)
+    }
+}
@@ -0,0 +1,237 @@
+use meadorc::{compiler::MeadorCompiler, runtime::ExecutionContext, value::Value};
+
+use anyhow::Result;
+
+fn array(values: &[i64]) -> Value {
+    values
+        .iter()
+        .map(|value| Value::Integer(*value))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+#[test]
+fn test_array_literal_and_indexing() -> Result<()> {
+    let code = "let a = [1, 2 + 3, sin(0)];
+                let first = a[0];
+                let last = a[len(a) - 1];
+                let nested = [[1, 2], [3, 4]][1][0];
+                let empty = len([]);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"a".to_string()),
+        Some(&Value::from(vec![
+            Value::Integer(1),
+            Value::Integer(5),
+            Value::Numerical(0.0)
+        ]))
+    );
+    assert_eq!(
+        context.get_variable(&"first".to_string()),
+        Some(&Value::Integer(1))
+    );
+    assert_eq!(
+        context.get_variable(&"last".to_string()),
+        Some(&Value::Numerical(0.0))
+    );
+    assert_eq!(
+        context.get_variable(&"nested".to_string()),
+        Some(&Value::Integer(3))
+    );
+    assert_eq!(
+        context.get_variable(&"empty".to_string()),
+        Some(&Value::Integer(0))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_array_index_assignment() -> Result<()> {
+    let code = "let a = [1, 2, 3];
+                let alias = a;
+                a[0] = 10;
+                let grid = [[0, 0], [0, 0]];
+                grid[1][0] = alias[0] + 1;"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"alias".to_string()),
+        Some(&array(&[10, 2, 3]))
+    );
+    assert_eq!(
+        context.get_variable(&"grid".to_string()),
+        Some(&Value::from(vec![array(&[0, 0]), array(&[11, 0])]))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_array_builtins() -> Result<()> {
+    let code = "let readings = [3, 1, 4];
+                push(readings, 1);
+                push(readings, 5);
+                let last = pop(readings);
+                let middle = slice(readings, 1, 3);
+                fn square(x) {
+                    return x * x;
+                }
+                let squares = map(readings, square);
+                let total = sum(squares);
+                let sines = map([0], sin);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    let expected = [
+        ("readings", array(&[3, 1, 4, 1])),
+        ("last", Value::Integer(5)),
+        ("middle", array(&[1, 4])),
+        ("squares", array(&[9, 1, 16, 1])),
+        ("total", Value::Integer(27)),
+        ("sines", Value::from(vec![Value::Numerical(0.0)])),
+    ];
+
+    for (name, value) in expected {
+        assert_eq!(
+            context.get_variable(&name.to_string()),
+            Some(&value),
+            "{}",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_array_equality_and_printing() -> Result<()> {
+    let code = r#"let same = [1, "a", [true]] == [1, "a", [true]];
+                  let different = [1, 2] != [1, 2, 3];
+                  print([1, "a", [true]], []);"#;
+
+    let program = MeadorCompiler::compile(code)?;
+    let (mut context, output) = ExecutionContext::buffered();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"same".to_string()),
+        Some(&Value::Boolean(true))
+    );
+    assert_eq!(
+        context.get_variable(&"different".to_string()),
+        Some(&Value::Boolean(true))
+    );
+    assert_eq!(output.contents(), "[1, \"a\", [true]] []\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_self_containing_collections() -> Result<()> {
+    let code = r#"let a = [1];
+                  push(a, a);
+                  let b = [1];
+                  push(b, b);
+                  let c = [2];
+                  push(c, c);
+                  let m = { key: "value" };
+                  m.self = m;
+                  let same = a == a;
+                  let alike = a == b;
+                  let different = a != c;
+                  let maps = m == m;
+                  println(a, m);"#;
+
+    let program = MeadorCompiler::compile(code)?;
+
+    for bytecode in [false, true] {
+        let (mut context, output) = ExecutionContext::buffered();
+
+        if bytecode {
+            program.to_bytecode().execute(&mut context)?;
+        } else {
+            program.execute(&mut context)?;
+        }
+
+        for name in ["same", "alike", "different", "maps"] {
+            assert_eq!(
+                context.get_variable(&name.to_string()),
+                Some(&Value::Boolean(true)),
+                "{}",
+                name
+            );
+        }
+
+        assert_eq!(
+            output.contents(),
+            "[1, [...]] {key: \"value\", self: {...}}\n"
+        );
+    }
+
+    let error =
+        MeadorCompiler::compile("let a = [1]; push(a, a); fn f(v) { return v + 1; } f(a);")?
+            .execute(&mut ExecutionContext::null())
+            .expect_err("adding to an array should fail");
+
+    assert!(error.to_string().contains("Array([...])"), "{}", error);
+
+    Ok(())
+}
+
+#[test]
+fn test_array_errors() -> Result<()> {
+    let cases = [
+        (
+            "let a = [1, 2, 3]; print(a[3]);",
+            "Index 3 out of bounds for array of length 3",
+        ),
+        (
+            "let a = [1]; let i = -1; a[i] = 0;",
+            "Index -1 out of bounds for array of length 1",
+        ),
+        (
+            "let a = [1]; print(a[0.5]);",
+            "Array index must be a whole number, got 0.5",
+        ),
+        ("let a = []; pop(a);", "Cannot pop from an empty array"),
+        (
+            "print(slice([1, 2], 1, 3));",
+            "Slice bound 3 out of range for array of length 2",
+        ),
+    ];
+
+    for (code, message) in cases {
+        let error = MeadorCompiler::compile(code)?
+            .execute(&mut ExecutionContext::null())
+            .expect_err(code);
+
+        assert!(error.to_string().contains(message), "{}: {}", code, error);
+    }
+
+    let error = MeadorCompiler::compile("let x = 1; let y = x[0]; let z = [1][true];")
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(error.contains("Cannot index into number"));
+    assert!(error.contains("Array index must be a number, found boolean"));
+
+    Ok(())
+}
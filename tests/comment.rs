@@ -0,0 +1,234 @@
+use meadorc::{
+    compiler::{DeclarationKind, MeadorCompiler},
+    runtime::ExecutionContext,
+    value::Value,
+};
+
+use anyhow::Result;
+
+#[test]
+fn test_line_and_block_comments() -> Result<()> {
+    let code = r#"// Leading comment.
+                  let x = 1; // Trailing comment.
+                  /* Block comment
+                     spanning lines. */
+                  let y = x /* inline */ + 2;
+                  /* Outer /* nested */ still a comment */
+                  //// Four slashes make a plain comment.
+                  let url = "http://example.com /* not a comment */";
+                  let ratio = 6 / 3;
+                  print(y); // Comment before the end of input"#;
+
+    let program = MeadorCompiler::compile(code)?;
+    let (mut context, output) = ExecutionContext::buffered();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"y".to_string()),
+        Some(&Value::Integer(3))
+    );
+    assert_eq!(
+        context.get_variable(&"url".to_string()),
+        Some(&Value::from("http://example.com /* not a comment */"))
+    );
+    assert_eq!(
+        context.get_variable(&"ratio".to_string()),
+        Some(&Value::Numerical(2.0))
+    );
+    assert_eq!(output.contents(), "3\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_doc_comments_on_declarations() -> Result<()> {
+    let code = "/// Rate applied to every order.
+                ///
+                ///   Indented line.
+                let rate = 2;
+
+                /// Scales an amount by the rate.
+                fn scale(amount) {
+                    /// Not exported.
+                    let scaled = amount * rate;
+                    return scaled;
+                }
+
+                // A plain comment is not documentation.
+                let total = scale(3);";
+
+    let program = MeadorCompiler::compile(code)?;
+    let declarations = program.declarations();
+
+    let summary: Vec<_> = declarations
+        .iter()
+        .map(|declaration| {
+            (
+                declaration.name.as_str(),
+                declaration.kind,
+                declaration.doc.as_deref(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        summary,
+        vec![
+            (
+                "rate",
+                DeclarationKind::Variable,
+                Some("Rate applied to every order.\n\n  Indented line.")
+            ),
+            (
+                "scale",
+                DeclarationKind::Function,
+                Some("Scales an amount by the rate.")
+            ),
+            ("scaled", DeclarationKind::Variable, Some("Not exported.")),
+            ("total", DeclarationKind::Variable, None),
+        ]
+    );
+
+    // Spans start at the declaration itself, after its doc comment.
+    let scale = &declarations[1];
+    assert_eq!(scale.span.start.line, 7);
+    assert_eq!(scale.span.start.column, 17);
+
+    let mut context = ExecutionContext::null();
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"total".to_string()),
+        Some(&Value::Integer(6))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_stray_doc_comments() -> Result<()> {
+    let code = "let x = 1;
+                /// Documents an assignment, which is not a declaration.
+                x = 2;
+                {
+                    /// Ends a block.
+                }
+                /// Ends the program.";
+
+    let program = MeadorCompiler::compile(code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(program.declarations()[0].doc, None);
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(2))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_doc_comments_inside_expressions() -> Result<()> {
+    let code = "let x = 4;
+                let y = 1 + /// note
+                        2;
+                println(x /// note
+                );
+                /// Before a call.
+                println(y);";
+
+    let program = MeadorCompiler::compile(code)?;
+    let (mut context, output) = ExecutionContext::buffered();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"y".to_string()),
+        Some(&Value::Integer(3))
+    );
+    assert_eq!(output.contents(), "4\n3\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_line_comments_and_floor_division() -> Result<()> {
+    let code = "let half = 7 // 2; // Floor division.
+                let sum = half + 1; // 4
+                let parts = [7 // 2, 7 % 2];
+                if sum > 10 { // Not taken.
+                    sum = 0;
+                } // Before `else`.
+                else {
+                    sum = sum // 2;
+                }";
+
+    let program = MeadorCompiler::compile(code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"half".to_string()),
+        Some(&Value::Integer(3))
+    );
+    assert_eq!(
+        context.get_variable(&"sum".to_string()),
+        Some(&Value::Integer(2))
+    );
+    assert_eq!(
+        context
+            .get_variable(&"parts".to_string())
+            .map(Value::to_string),
+        Some("[3, 1]".to_string())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_line_comments_between_tokens() -> Result<()> {
+    let code = "let a = [1, // one
+                         2];
+                fn half(n) // Rounds down.
+                {
+                    return n // 2;
+                }
+                let b = half( // Argument follows.
+                    a[1] * 5
+                );
+                let c = a[0] + // Operand follows.
+                        b;";
+
+    let program = MeadorCompiler::compile(code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"a".to_string()).map(Value::to_string),
+        Some("[1, 2]".to_string())
+    );
+    assert_eq!(
+        context.get_variable(&"b".to_string()),
+        Some(&Value::Integer(5))
+    );
+    assert_eq!(
+        context.get_variable(&"c".to_string()),
+        Some(&Value::Integer(6))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_unterminated_block_comment() {
+    let error = MeadorCompiler::compile("let x = 1; /* never closed\nlet y = 2;")
+        .err()
+        .unwrap();
+
+    assert!(error.to_string().contains("line 1"), "{}", error);
+}
@@ -0,0 +1,13 @@
+use meadorc::{compiler::MeadorCompiler, runtime::ExecutionContext};
+
+use anyhow::Result;
+
+/// Compiles and runs `code`, returning the context it leaves behind.
+pub fn evaluate(code: &str) -> Result<ExecutionContext> {
+    let program = MeadorCompiler::compile(code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    Ok(context)
+}
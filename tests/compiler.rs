@@ -1,4 +1,8 @@
-use meadorc::{compiler::MeadorCompiler, runtime::ExecutionContext, value::Value};
+use meadorc::{
+    compiler::{CompilationError, MeadorCompiler},
+    runtime::ExecutionContext,
+    value::Value,
+};
 
 use anyhow::Result;
 
@@ -7,7 +11,7 @@ fn test_complex_math() -> Result<()> {
     let code = "let x = (6 / (1 + 2 ^ 2)) * 2;".to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context)?;
 
@@ -27,13 +31,13 @@ fn test_function_call() -> Result<()> {
         .to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context)?;
 
     assert_eq!(
         context.get_variable(&"x".to_string()),
-        Some(&Value::Numerical(2.0))
+        Some(&Value::Integer(2))
     );
     assert_eq!(
         context.get_variable(&"y".to_string()),
@@ -55,13 +59,13 @@ fn test_negative_numbers() -> Result<()> {
         .to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context)?;
 
     assert_eq!(
         context.get_variable(&"x".to_string()),
-        Some(&Value::Numerical(-2.0))
+        Some(&Value::Integer(-2))
     );
     assert_eq!(
         context.get_variable(&"y".to_string()),
@@ -88,10 +92,14 @@ fn test_invalid_program_compilation() -> Result<()> {
 
 #[test]
 fn test_runtime_error() -> Result<()> {
-    let code = "let x = 5 + true;".to_string();
+    let code = "fn add(a, b) {
+                    return a + b;
+                }
+                let x = add(5, true);"
+        .to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     let result = program.execute(&mut context);
 
@@ -99,3 +107,82 @@ fn test_runtime_error() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_type_error() -> Result<()> {
+    let code = "let x = 5 + true;".to_string();
+
+    let result = MeadorCompiler::compile(&code);
+
+    assert!(matches!(result, Err(CompilationError::Type { .. })));
+
+    Ok(())
+}
+
+#[test]
+fn test_type_errors_reported_together() -> Result<()> {
+    let code = r#"print("never printed");
+                let x = 1;
+                if x {
+                    x = "text";
+                }
+                while sin(1, 2) {}
+                let y = cos(true) + "s";"#
+        .to_string();
+
+    let errors = match MeadorCompiler::compile(&code) {
+        Err(CompilationError::Multiple(errors)) => errors,
+        _ => panic!("expected several type errors"),
+    };
+
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+
+    assert_eq!(messages.len(), 6, "{:#?}", messages);
+    assert!(messages[0].contains("`if` condition must be boolean, found number"));
+    assert!(messages[1].contains("Cannot assign string to variable `x` of type number"));
+    assert!(messages[2].contains("Function `sin` expects 1 argument, got 2"));
+    assert!(messages[3].contains("`while` condition must be boolean, found number"));
+    assert!(messages[4].contains("Argument 1 of `cos` must be number, found boolean"));
+    assert!(messages[5].contains("number and string"));
+
+    Ok(())
+}
+
+#[test]
+fn test_type_inference_through_variables() -> Result<()> {
+    let code = r#"let name = "meador";
+                let size = 2;
+                let valid = name + "c" == "meadorc" && size > 1;
+                fn shout(text) {
+                    return text + "!";
+                }
+                let loud = shout(name);"#
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"valid".to_string()),
+        Some(&Value::Boolean(true))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_user_function_arity_checked() -> Result<()> {
+    let code = "fn add(a, b) {
+                    return a + b;
+                }
+                let x = add(1);"
+        .to_string();
+
+    let result = MeadorCompiler::compile(&code);
+
+    assert!(matches!(result, Err(CompilationError::Type { .. })));
+
+    Ok(())
+}
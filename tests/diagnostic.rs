@@ -0,0 +1,159 @@
+use meadorc::{
+    compiler::{CompilationError, MeadorCompiler},
+    diagnostic::Diagnostic,
+    span::Span,
+};
+
+#[test]
+fn test_syntax_error_keeps_expectations() {
+    let result = MeadorCompiler::compile("let x = 1;\nlet y = x +;");
+
+    match result {
+        Err(CompilationError::Syntax {
+            expected, found, ..
+        }) => {
+            assert_eq!(expected, vec!["value".to_string()]);
+            assert_eq!(found, "`;`");
+        }
+        other => panic!("expected a syntax error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn test_syntax_error_at_end_of_input() {
+    let error = MeadorCompiler::compile("let x = 1").err().unwrap();
+
+    assert!(error.to_string().contains("found end of input"));
+
+    let diagnostics = error.diagnostics();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].help.is_some());
+}
+
+#[test]
+fn test_render_syntax_error() {
+    let code = "print(1);\nlet y = 2 +* 3;";
+    let error = MeadorCompiler::compile(code).err().unwrap();
+
+    let rendered = error.diagnostics()[0].render(code);
+
+    assert_eq!(
+        rendered,
+        "error: expected value, found `*`\n \
+         --> 2:12\n  \
+         |\n\
+         2 | let y = 2 +* 3;\n  \
+         |            ^ expected value\n  \
+         |\n  \
+         = expected: value\n  \
+         = found: `*`\n"
+    );
+}
+
+#[test]
+fn test_render_type_error_underlines_expression() {
+    let code = "let flag = true;\nlet x = flag + 1;";
+    let error = MeadorCompiler::compile(code).err().unwrap();
+
+    let rendered = error.diagnostics()[0].render(code);
+
+    assert!(rendered.starts_with("error: Type error: Invalid types"));
+    assert!(rendered.contains("2 | let x = flag + 1;\n  |         ^^^^^^^^\n"));
+}
+
+#[test]
+fn test_render_notes_and_help() {
+    let code = "let total = price * 2;";
+    let diagnostic = Diagnostic::error("Unknown variable: price")
+        .with_span(Span::new(12, 17))
+        .with_label("not declared")
+        .with_note("variables must be declared with `let` before use")
+        .with_help("declare it first: `let price = 0;`");
+
+    assert_eq!(
+        diagnostic.render(code),
+        "error: Unknown variable: price\n \
+         --> 1:13\n  \
+         |\n\
+         1 | let total = price * 2;\n  \
+         |             ^^^^^ not declared\n  \
+         |\n  \
+         = note: variables must be declared with `let` before use\n  \
+         = help: declare it first: `let price = 0;`\n"
+    );
+}
+
+#[test]
+fn test_diagnostic_json() {
+    let code = "let x = 1;\nlet s = \"a\" ++ 1;";
+    let error = MeadorCompiler::compile(code).err().unwrap();
+
+    let json = error.diagnostics()[0].to_json(code);
+
+    assert_eq!(
+        json,
+        "{\"severity\":\"error\",\"message\":\"expected value, found `+`\",\
+         \"span\":{\"start\":24,\"end\":25,\"line\":2,\"column\":14,\"end_line\":2,\"end_column\":15},\
+         \"label\":\"expected value\",\"expected\":[\"value\"],\"found\":\"`+`\",\
+         \"notes\":[],\"help\":null}"
+    );
+}
+
+#[test]
+fn test_multiple_errors_give_multiple_diagnostics() {
+    let code = "let x = 1 + true;\nlet y = \"a\" - 1;";
+    let error = MeadorCompiler::compile(code).err().unwrap();
+
+    let diagnostics = error.diagnostics();
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].span, Some(Span::new(8, 16)));
+    assert_eq!(diagnostics[1].span, Some(Span::new(26, 33)));
+}
+
+#[test]
+fn test_recovers_after_syntax_errors() {
+    let code = r#"let x = ;
+                fn f(a) {
+                    let y = a +* 2;
+                    print(y);
+                }
+                }
+                let s = "semi;colon" +;
+                let ok = true + 1;"#;
+
+    let errors = MeadorCompiler::compile(code).err().unwrap().into_errors();
+
+    let found: Vec<String> = errors
+        .iter()
+        .map(|error| match error {
+            CompilationError::Syntax { found, .. } => found.clone(),
+            CompilationError::Type { .. } => "type".to_string(),
+            other => panic!("unexpected error {}", other),
+        })
+        .collect();
+
+    assert_eq!(found, vec!["`;`", "`*`", "`}`", "`;`", "type"]);
+
+    let lines: Vec<usize> = errors
+        .iter()
+        .map(|error| error.location().unwrap().start.line)
+        .collect();
+
+    assert_eq!(lines, vec![1, 3, 6, 7, 8]);
+}
+
+#[test]
+fn test_errors_ordered_by_position() {
+    let code = "let a = 1 + true;\nif a > { print(a); }\nlet b = \"a\" - 1;\nlet c = ;";
+    let errors = MeadorCompiler::compile(code).err().unwrap().into_errors();
+
+    let starts: Vec<usize> = errors
+        .iter()
+        .map(|error| error.location().unwrap().span.start)
+        .collect();
+
+    assert_eq!(errors.len(), 4, "{:#?}", errors);
+    assert!(starts.windows(2).all(|pair| pair[0] <= pair[1]));
+}
@@ -7,7 +7,7 @@ fn test_numeric_expression() -> Result<()> {
     let code = "let x = 2.4;".to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context)?;
 
@@ -24,7 +24,7 @@ fn test_functional_expression() -> Result<()> {
     let code = "let x = sin(2.4);".to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context).unwrap();
 
@@ -41,7 +41,7 @@ fn test_boolean_expression() -> Result<()> {
     let code = "let x = 2 > 1;".to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context)?;
 
@@ -58,7 +58,7 @@ fn test_boolean_binary_expression() -> Result<()> {
     let code = "let x = 2 > 1 && 3 < 4;".to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context).unwrap();
 
@@ -75,13 +75,13 @@ fn test_parenthesis_expression() -> Result<()> {
     let code = "let x = (2 + 2) * 2;".to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context).unwrap();
 
     assert_eq!(
         context.get_variable(&"x".to_string()),
-        Some(&Value::Numerical(8.0))
+        Some(&Value::Integer(8))
     );
 
     Ok(())
@@ -92,13 +92,13 @@ fn test_binary_expression() -> Result<()> {
     let code = "let x = 2 + 2 * 2;".to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context).unwrap();
 
     assert_eq!(
         context.get_variable(&"x".to_string()),
-        Some(&Value::Numerical(6.0))
+        Some(&Value::Integer(6))
     );
 
     Ok(())
@@ -118,7 +118,274 @@ fn test_incopatible_types_binary_expression() {
     let code = "let x = 2 + true;".to_string();
 
     let program = MeadorCompiler::compile(&code).unwrap();
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context).unwrap();
 }
+
+fn evaluate_equality(code: &str) -> Result<(Value, Value)> {
+    let program = MeadorCompiler::compile(code)?;
+    let (mut context, output) = ExecutionContext::buffered();
+
+    program.execute(&mut context)?;
+
+    // Each `print()` operand still runs, writing an empty line.
+    assert_eq!(
+        output.contents(),
+        "\n".repeat(code.matches("print()").count())
+    );
+
+    let eq = context.get_variable(&"eq".to_string()).cloned().unwrap();
+    let ne = context.get_variable(&"ne".to_string()).cloned().unwrap();
+
+    Ok((eq, ne))
+}
+
+#[test]
+fn test_equality_expression() -> Result<()> {
+    let code = "let x = 1;
+                let y = 0;
+                if x == 1 {
+                    y = 2;
+                }"
+    .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"y".to_string()),
+        Some(&Value::Integer(2))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_equality_same_types() -> Result<()> {
+    let cases = [
+        ("1", "1", true),
+        ("1", "2", false),
+        ("2.5", "2.5", true),
+        ("true", "true", true),
+        ("true", "false", false),
+        ("false", "false", true),
+        ("print()", "print()", true),
+    ];
+
+    for (left, right, expected) in cases {
+        let code = format!("let eq = {left} == {right}; let ne = {left} != {right};");
+
+        let (eq, ne) = evaluate_equality(&code)?;
+
+        assert_eq!(eq, Value::Boolean(expected), "{left} == {right}");
+        assert_eq!(ne, Value::Boolean(!expected), "{left} != {right}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_equality_mixed_types() -> Result<()> {
+    let cases = [
+        ("1", "true"),
+        ("true", "1"),
+        ("0", "false"),
+        ("1", "print()"),
+        ("print()", "0"),
+        ("true", "print()"),
+        ("print()", "false"),
+    ];
+
+    for (left, right) in cases {
+        let code = format!("let eq = {left} == {right}; let ne = {left} != {right};");
+
+        let (eq, ne) = evaluate_equality(&code)?;
+
+        assert_eq!(eq, Value::Boolean(false), "{left} == {right}");
+        assert_eq!(ne, Value::Boolean(true), "{left} != {right}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_equality_nan() -> Result<()> {
+    let code = "let nan = 0.0 / 0.0;
+                let eq = nan == nan;
+                let ne = nan != nan;";
+
+    let (eq, ne) = evaluate_equality(code)?;
+
+    assert_eq!(eq, Value::Boolean(false));
+    assert_eq!(ne, Value::Boolean(true));
+
+    Ok(())
+}
+
+#[test]
+fn test_equality_signed_zero() -> Result<()> {
+    let code = "let eq = 0.0 == -0.0;
+                let ne = -0.0 != 0.0;";
+
+    let (eq, ne) = evaluate_equality(code)?;
+
+    assert_eq!(eq, Value::Boolean(true));
+    assert_eq!(ne, Value::Boolean(false));
+
+    Ok(())
+}
+
+#[test]
+fn test_string_expression() -> Result<()> {
+    let code = r#"let x = "hello";"#.to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::from("hello"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_string_escapes() -> Result<()> {
+    let code = r#"let x = "quote \" slash \\ tab \t line \n nul \0 smile \u{1F600}";"#.to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::from(
+            "quote \" slash \\ tab \t line \n nul \0 smile \u{1F600}"
+        ))
+    );
+
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn test_invalid_unicode_escape() {
+    let code = r#"let x = "\u{110000}";"#.to_string();
+
+    MeadorCompiler::compile(&code).unwrap();
+}
+
+#[test]
+fn test_string_concatenation() -> Result<()> {
+    let code = r#"let x = "foo" + "bar" + "";"#.to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::from("foobar"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_string_comparison() -> Result<()> {
+    let cases = [
+        (r#""abc" < "abd""#, true),
+        (r#""abc" <= "abc""#, true),
+        (r#""b" > "abc""#, true),
+        (r#""abc" >= "abd""#, false),
+        (r#""abc" == "abc""#, true),
+        (r#""abc" != "abc""#, false),
+        (r#""1" == 1"#, false),
+        (r#""true" != true"#, true),
+    ];
+
+    for (expression, expected) in cases {
+        let code = format!("let x = {};", expression);
+
+        let program = MeadorCompiler::compile(&code)?;
+        let mut context = ExecutionContext::null();
+
+        program.execute(&mut context)?;
+
+        assert_eq!(
+            context.get_variable(&"x".to_string()),
+            Some(&Value::Boolean(expected)),
+            "{}",
+            expression
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_string_operators() -> Result<()> {
+    for code in [r#"let x = "a" + 1;"#, r#"let x = "a" * "b";"#] {
+        assert!(MeadorCompiler::compile(code).is_err(), "{}", code);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_unary_expression() -> Result<()> {
+    let code = "let x = 3;
+                let a = -x;
+                let b = -(x + 1) * 2;
+                let c = 2-1;
+                let d = -2 ^ 2;
+                let e = 2 ^ -1;
+                let f = !(x > 1) || !false;
+                let g = 1 - -sin(0);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    let expected = [
+        ("a", Value::Integer(-3)),
+        ("b", Value::Integer(-8)),
+        ("c", Value::Integer(1)),
+        ("d", Value::Integer(-4)),
+        ("e", Value::Numerical(0.5)),
+        ("f", Value::Boolean(true)),
+        ("g", Value::Numerical(1.0)),
+    ];
+
+    for (name, value) in expected {
+        assert_eq!(
+            context.get_variable(&name.to_string()),
+            Some(&value),
+            "{}",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_unary_operands() {
+    let error = MeadorCompiler::compile("let a = -true;\nlet b = !\"text\";")
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(error.contains("Invalid type for unary operator `Negation`: boolean"));
+    assert!(error.contains("Invalid type for unary operator `Not`: string"));
+}
@@ -0,0 +1,107 @@
+use meadorc::{compiler::MeadorCompiler, runtime::ExecutionContext, value::Value};
+
+use anyhow::Result;
+
+fn format(arguments: &str) -> Result<String> {
+    let code = format!("let x = format({});", arguments);
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    match context.get_variable(&"x".to_string()) {
+        Some(Value::Str(string)) => Ok(string.to_string()),
+        other => panic!("`{}` gave {:?}", code, other),
+    }
+}
+
+#[test]
+fn test_format_placeholders() -> Result<()> {
+    let cases = [
+        (r#""x = {:.3}", 2 / 3"#, "x = 0.667"),
+        (r#""{} and {}", 1, "two""#, "1 and two"),
+        (r#""{:.2}", 7"#, "7.00"),
+        (r#""{:.0}", 2.5"#, "2"),
+        (r#""[{:5}]", 42"#, "[   42]"),
+        (r#""[{:5}]", "ab""#, "[ab   ]"),
+        (
+            r#""[{:>5}|{:<5}|{:^5}]", "a", 1, "b""#,
+            "[    a|1    |  b  ]",
+        ),
+        (r#""[{:*^7}]", "mid""#, "[**mid**]"),
+        (r#""{:+}, {:+.1}", 3, -0.25"#, "+3, -0.2"),
+        (r#""{:06.2}", -1.5"#, "-01.50"),
+        (r#""{:.3}", "truncated""#, "tru"),
+        (r#""{:?} {}", "quoted", "plain""#, "\"quoted\" plain"),
+        (r#""{:>10?}", "s\n""#, "     \"s\\n\""),
+        (r#""{:?}", [1, "a"]"#, "[1, \"a\"]"),
+        (r#""{{}} {{{}}}", 5,"#, "{} {5}"),
+        (r#""no placeholders""#, "no placeholders"),
+    ];
+
+    for (arguments, expected) in cases {
+        assert_eq!(format(arguments)?, expected, "{}", arguments);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_format_errors() -> Result<()> {
+    let cases = [
+        (r#""{} {}", 1"#, "Format string expects 2 arguments, got 1"),
+        (r#""{}", 1, 2"#, "Format string expects 1 argument, got 2"),
+        (r#""{:x}", 1"#, "Invalid format placeholder `{:x}`"),
+        (r#""{0}", 1"#, "Invalid format placeholder `{0}`"),
+        (r#""{:.}", 1"#, "Invalid format placeholder `{:.}`"),
+        (
+            r#""{:99999999999}", 1"#,
+            "Format width 99999999999 exceeds the maximum of 65535",
+        ),
+        (
+            r#""{:.65536}", 1.5"#,
+            "Format precision 65536 exceeds the maximum of 65535",
+        ),
+        (
+            r#""[{:99999999999999999999}]", 1"#,
+            "Invalid format placeholder `{:99999999999999999999}`",
+        ),
+        (
+            r#""{:.99999999999999999999}", 1.5"#,
+            "Invalid format placeholder `{:.99999999999999999999}`",
+        ),
+        (r#""{", 1"#, "Unclosed `{` in format string"),
+        (r#""}""#, "Unmatched `}` in format string"),
+    ];
+
+    assert_eq!(format(r#""{:65535}", 1"#)?.len(), 65535);
+
+    for (arguments, message) in cases {
+        let error = format(arguments).expect_err(arguments);
+
+        assert!(
+            error.to_string().contains(message),
+            "{}: {}",
+            arguments,
+            error
+        );
+    }
+
+    let error = MeadorCompiler::compile("let x = format(); let y = format(1);")
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(
+        error.contains("Function `format` expects at least 1 argument, got 0"),
+        "{}",
+        error
+    );
+    assert!(
+        error.contains("Argument 1 of `format` must be string, found number"),
+        "{}",
+        error
+    );
+
+    Ok(())
+}
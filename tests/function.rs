@@ -0,0 +1,263 @@
+use meadorc::{compiler::MeadorCompiler, runtime::ExecutionContext, value::Value};
+
+use anyhow::Result;
+
+#[test]
+fn test_function_declaration() -> Result<()> {
+    let code = "fn add(a, b) {
+                    return a + b;
+                }
+                let x = add(2, 3);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(5))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_recursive_function() -> Result<()> {
+    let code = "fn factorial(n) {
+                    if n <= 1 {
+                        return 1;
+                    }
+                    return n * factorial(n - 1);
+                }
+                let x = factorial(5);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(120))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_function_without_return_value() -> Result<()> {
+    let code = "fn nothing() {
+                    let y = 1;
+                    return;
+                }
+                fn empty() {}
+                let x = nothing();
+                let z = empty();"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(context.get_variable(&"x".to_string()), Some(&Value::Void));
+    assert_eq!(context.get_variable(&"z".to_string()), Some(&Value::Void));
+
+    Ok(())
+}
+
+#[test]
+fn test_function_call_frame() -> Result<()> {
+    let code = "let offset = 10;
+                let a = 1;
+                fn shift(a) {
+                    let local = a + offset;
+                    return local;
+                }
+                let x = shift(5);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(15))
+    );
+    assert_eq!(
+        context.get_variable(&"a".to_string()),
+        Some(&Value::Integer(1))
+    );
+    assert_eq!(context.get_variable(&"local".to_string()), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_user_function_shadows_builtin() -> Result<()> {
+    let code = "fn sin(x) {
+                    return 42;
+                }
+                let x = sin(0);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(42))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_function_arity_mismatch() -> Result<()> {
+    let code = "fn add(a, b) {
+                    return a + b;
+                }
+                let x = add(1);"
+        .to_string();
+
+    let result = MeadorCompiler::compile(&code);
+
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_function_names_starting_with_keywords() -> Result<()> {
+    let code = "let calls = 0;
+                fn returnValue() {
+                    calls = calls + 1;
+                    return calls;
+                }
+                fn fnord(breakfast, continued) {
+                    return breakfast + continued;
+                }
+                returnValue();
+                let x = fnord(returnValue(), 10);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"calls".to_string()),
+        Some(&Value::Integer(2))
+    );
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(12))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_unbounded_recursion() -> Result<()> {
+    let code = "fn forever(n) {
+                    return forever(n + 1);
+                }
+                let x = forever(0);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    let result = program.execute(&mut context);
+
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_comma_separated_arguments() -> Result<()> {
+    let code = "fn subtract(a, b) {
+                    return a - b;
+                }
+                let x = subtract(10, 4,);
+                let y = subtract(1 -2, -3);
+                let z = slice(
+                    [1, 2, 3],
+                    1,
+                    3,
+                );"
+    .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(6))
+    );
+    assert_eq!(
+        context.get_variable(&"y".to_string()),
+        Some(&Value::Integer(2))
+    );
+    assert_eq!(
+        context.get_variable(&"z".to_string()),
+        Some(&Value::from(vec![Value::Integer(2), Value::Integer(3)]))
+    );
+
+    for code in [
+        "let x = subtract(1 2);",
+        "let x = sin(,);",
+        "let x = sin(1,,);",
+    ] {
+        assert!(MeadorCompiler::compile(code).is_err(), "{}", code);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_builtin_arity() -> Result<()> {
+    let error = MeadorCompiler::compile("let x = sin(1, 2);")
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(
+        error.contains("Function `sin` expects 1 argument, got 2"),
+        "{}",
+        error
+    );
+
+    let program = MeadorCompiler::compile("let x = map([[1]], push);")?;
+
+    let interpreted = program
+        .execute(&mut ExecutionContext::null())
+        .expect_err("interpreter should fail");
+    let compiled = program
+        .to_bytecode()
+        .execute(&mut ExecutionContext::null())
+        .expect_err("VM should fail");
+
+    for error in [interpreted, compiled] {
+        assert!(
+            error
+                .to_string()
+                .contains("Function `push` expects 2 arguments, got 1"),
+            "{}",
+            error
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use meadorc::{
+    compiler::MeadorCompiler,
+    runtime::{Arity, ExecutionContext},
+    value::Value,
+};
+
+use anyhow::Result;
+
+#[test]
+fn test_host_function() -> Result<()> {
+    let code = "let x = lookup_rate(2);".to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    context.register_function("lookup_rate", 1, |arguments| {
+        let amount = f64::try_from(&arguments[0])?;
+        Ok((amount * 1.5).into())
+    });
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Numerical(3.0))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_variadic_host_function() -> Result<()> {
+    let code = r#"log_event("start");
+                  log_event("tick", 1, true);"#
+        .to_string();
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let sink = events.clone();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    context.register_function("log_event", Arity::AtLeast(1), move |arguments| {
+        let name = String::try_from(&arguments[0])?;
+        sink.borrow_mut().push((name, arguments.len() - 1));
+        Ok(().into())
+    });
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        *events.borrow(),
+        vec![("start".to_string(), 0), ("tick".to_string(), 2)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_host_function_overrides_builtin() -> Result<()> {
+    let code = "let x = sin(1);".to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    context.register_function("sin", 1, |_| Ok(Value::Numerical(0.5)));
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Numerical(0.5))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_host_function_overrides_builtin_signature() -> Result<()> {
+    let code = "let x = len(\"ab\", 3);";
+
+    assert!(MeadorCompiler::compile(code)
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("Function `len` expects 1 argument, got 2"));
+
+    let mut context = ExecutionContext::null();
+    context.register_function("len", 2, |arguments| {
+        Ok(Value::Integer(arguments[0].to_string().len() as i64 * 10))
+    });
+
+    MeadorCompiler::compile_for(code, &context)?.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(20))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_user_function_overrides_host_function() -> Result<()> {
+    let code = "fn rate(x) {
+                    return x;
+                }
+                let x = rate(7);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    context.register_function("rate", 1, |_| Ok(Value::Numerical(0.0)));
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(7))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_host_function_arity() -> Result<()> {
+    let code = "let x = lookup_rate(1, 2);".to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    context.register_function("lookup_rate", 1, |_| Ok(Value::Void));
+
+    let result = program.execute(&mut context);
+
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("expects 1 argument, got 2"));
+
+    Ok(())
+}
+
+#[test]
+fn test_host_function_errors() -> Result<()> {
+    let code = "let x = lookup_rate(true);".to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    context.register_function("lookup_rate", 1, |arguments| {
+        Ok(f64::try_from(&arguments[0])?.into())
+    });
+
+    let result = program.execute(&mut context);
+
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Expected a number"));
+
+    Ok(())
+}
+
+#[test]
+fn test_short_circuit_skips_host_calls() -> Result<()> {
+    let code = "let a = false && probe(1);
+                let b = true || probe(2);
+                let c = true && probe(3);
+                let d = false || probe(4);
+                let x = 0;
+                let safe = x != 0 && 10 // x > 1;
+                let nested = false && probe(5) || true || probe(6);"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+
+    for use_vm in [false, true] {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let sink = calls.clone();
+
+        let mut context = ExecutionContext::null();
+        context.register_function("probe", 1, move |arguments| {
+            sink.borrow_mut().push(arguments[0].clone());
+            Ok(true.into())
+        });
+
+        if use_vm {
+            program.to_bytecode().execute(&mut context)?;
+        } else {
+            program.execute(&mut context)?;
+        }
+
+        assert_eq!(
+            *calls.borrow(),
+            vec![Value::Integer(3), Value::Integer(4)],
+            "vm: {}",
+            use_vm
+        );
+
+        let expected = [
+            ("a", false),
+            ("b", true),
+            ("c", true),
+            ("d", true),
+            ("safe", false),
+            ("nested", true),
+        ];
+
+        for (name, value) in expected {
+            assert_eq!(
+                context.get_variable(&name.to_string()),
+                Some(&Value::Boolean(value)),
+                "{} (vm: {})",
+                name,
+                use_vm
+            );
+        }
+    }
+
+    Ok(())
+}
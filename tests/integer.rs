@@ -0,0 +1,324 @@
+mod common;
+
+use meadorc::{compiler::MeadorCompiler, runtime::ExecutionContext, value::Value};
+
+use anyhow::Result;
+use common::evaluate;
+
+#[test]
+fn test_integer_literals_keep_precision() -> Result<()> {
+    let context = evaluate("let x = 9007199254740993; let y = x + 1 - 1;")?;
+
+    for name in ["x", "y"] {
+        let value = context.get_variable(&name.to_string());
+
+        assert!(
+            matches!(value, Some(Value::Integer(9007199254740993))),
+            "{}: {:?}",
+            name,
+            value
+        );
+        assert_ne!(value, Some(&Value::Numerical(9007199254740992.0)));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_integer_arithmetic_and_promotion() -> Result<()> {
+    let code = "let sum = 7 + 3 * 2 - 1;
+                let power = 2 ^ 62;
+                let mixed = 1 + 0.5;
+                let quotient = 7 / 2;
+                let exact = 6 / 3;
+                let reciprocal = 2 ^ -1;
+                let remainder = 7 % 3;
+                let length = len([1, 2, 3]);";
+
+    let context = evaluate(code)?;
+
+    let integers = [
+        ("sum", 12),
+        ("power", 4611686018427387904),
+        ("remainder", 1),
+        ("length", 3),
+    ];
+
+    for (name, expected) in integers {
+        let value = context.get_variable(&name.to_string());
+        assert!(
+            matches!(value, Some(Value::Integer(integer)) if *integer == expected),
+            "{}: {:?}",
+            name,
+            value
+        );
+    }
+
+    let floats = [
+        ("mixed", 1.5),
+        ("quotient", 3.5),
+        ("exact", 2.0),
+        ("reciprocal", 0.5),
+    ];
+
+    for (name, expected) in floats {
+        let value = context.get_variable(&name.to_string());
+        assert!(
+            matches!(value, Some(Value::Numerical(number)) if *number == expected),
+            "{}: {:?}",
+            name,
+            value
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_integer_remainder_signs() -> Result<()> {
+    let code = "let a = -7 % 3;
+                let b = 7 % -3;
+                let c = -7 % -3;
+                let d = 7.5 % 2;
+                let e = -7.5 % 2;
+                let f = 100000000000000000.0 % 3.0;
+                let g = -100000000000000000.0 % 3.0;";
+
+    let context = evaluate(code)?;
+
+    let expected = [
+        ("a", Value::Integer(2)),
+        ("b", Value::Integer(-2)),
+        ("c", Value::Integer(-1)),
+        ("d", Value::Numerical(1.5)),
+        ("e", Value::Numerical(0.5)),
+        ("f", Value::Numerical(1.0)),
+        ("g", Value::Numerical(2.0)),
+    ];
+
+    for (name, value) in expected {
+        assert_eq!(
+            context.get_variable(&name.to_string()),
+            Some(&value),
+            "{}",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_integer_equality_and_ranges() -> Result<()> {
+    let code = "let same = 1 == 1.0;
+                let rounded = 9007199254740993 == 9007199254740992.0;
+                let ordered = 2 < 2.5;
+                let below = 9007199254740993 < 9007199254740992.0;
+                let above = 9007199254740993 > 9007199254740992.0;
+                let larger = max(9007199254740992.0, 9007199254740993);
+                let smaller = min(9007199254740993, 9007199254740992.0);
+                let last = 0;
+                for i in 0..3 {
+                    last = i;
+                }
+                let fraction = 0;
+                for x in 0.5..2 {
+                    fraction = x;
+                }";
+
+    let context = evaluate(code)?;
+
+    assert_eq!(
+        context.get_variable(&"same".to_string()),
+        Some(&Value::Boolean(true))
+    );
+    assert_eq!(
+        context.get_variable(&"rounded".to_string()),
+        Some(&Value::Boolean(false))
+    );
+    assert_eq!(
+        context.get_variable(&"ordered".to_string()),
+        Some(&Value::Boolean(true))
+    );
+    assert_eq!(
+        context.get_variable(&"below".to_string()),
+        Some(&Value::Boolean(false))
+    );
+    assert_eq!(
+        context.get_variable(&"above".to_string()),
+        Some(&Value::Boolean(true))
+    );
+    assert_eq!(
+        context.get_variable(&"larger".to_string()),
+        Some(&Value::Integer(9007199254740993))
+    );
+    assert_eq!(
+        context.get_variable(&"smaller".to_string()),
+        Some(&Value::Numerical(9007199254740992.0))
+    );
+    assert_eq!(
+        context.get_variable(&"last".to_string()),
+        Some(&Value::Integer(2))
+    );
+    assert_eq!(
+        context.get_variable(&"fraction".to_string()),
+        Some(&Value::Numerical(1.5))
+    );
+
+    // Unlike `==` in scripts, `PartialEq` tells integers and floats apart.
+    assert_ne!(Value::Integer(1), Value::Numerical(1.0));
+    assert!(Value::Integer(1).structural_eq(&Value::Numerical(1.0)));
+
+    Ok(())
+}
+
+#[test]
+fn test_integer_errors() -> Result<()> {
+    let cases = [
+        (
+            "let x = 9223372036854775807 + 1;",
+            "Integer overflow in `Add`: 9223372036854775807 and 1",
+        ),
+        (
+            "let x = 3037000500 * 3037000500;",
+            "Integer overflow in `Multiply`: 3037000500 and 3037000500",
+        ),
+        ("let x = 2 ^ 63;", "Integer overflow in `Power`: 2 and 63"),
+        (
+            "let min = -9223372036854775807 - 1; let x = -min;",
+            "Integer overflow in `Negation`: -9223372036854775808",
+        ),
+        ("let x = 5 % 0;", "Integer remainder by zero: 5 % 0"),
+    ];
+
+    for (code, message) in cases {
+        let error = MeadorCompiler::compile(code)?
+            .execute(&mut ExecutionContext::null())
+            .expect_err(code);
+
+        assert!(error.to_string().contains(message), "{}: {}", code, error);
+    }
+
+    let error = MeadorCompiler::compile("let x = 9223372036854775808;")
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(error.contains("Failed to parse integer"), "{}", error);
+
+    Ok(())
+}
+
+#[test]
+fn test_floor_division_signs() -> Result<()> {
+    let code = "let a = 7 // 2;
+                let b = -7 // 2;
+                let c = 7 // -2;
+                let d = -7 // -2;
+                let e = 7.5 // 2;
+                let f = -7.5 // 2;
+                let g = -7 // 2 * 2 + -7 % 2;";
+
+    let context = evaluate(code)?;
+
+    let expected = [
+        ("a", Value::Integer(3)),
+        ("b", Value::Integer(-4)),
+        ("c", Value::Integer(-4)),
+        ("d", Value::Integer(3)),
+        ("e", Value::Numerical(3.0)),
+        ("f", Value::Numerical(-4.0)),
+        ("g", Value::Integer(-7)),
+    ];
+
+    for (name, value) in expected {
+        assert_eq!(
+            context.get_variable(&name.to_string()),
+            Some(&value),
+            "{}",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_bitwise_operators() -> Result<()> {
+    let code = "let and = 12 & 10;
+                let or = 12 | 10;
+                let exclusive = 12 xor 10;
+                let negative = -1 & 255;
+                let left = 1 << 62;
+                let wrapped = 3 << 63;
+                let right = 256 >> 4;
+                let signed = -16 >> 2;
+                let xored = 5 xor 5;
+                let xor_name = 1;
+                let hash = (xor_name << 5) xor 7;";
+
+    let context = evaluate(code)?;
+
+    let expected = [
+        ("and", 8),
+        ("or", 14),
+        ("exclusive", 6),
+        ("negative", 255),
+        ("left", 4611686018427387904),
+        ("wrapped", i64::MIN),
+        ("right", 16),
+        ("signed", -4),
+        ("xored", 0),
+        ("hash", 39),
+    ];
+
+    for (name, expected) in expected {
+        assert_eq!(
+            context.get_variable(&name.to_string()),
+            Some(&Value::Integer(expected)),
+            "{}",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_operator_errors() -> Result<()> {
+    let cases = [
+        ("let x = 5 // 0;", "Integer division by zero: 5 // 0"),
+        (
+            "let min = -9223372036854775807 - 1; let x = min // -1;",
+            "Integer overflow in `FloorDivide`: -9223372036854775808 and -1",
+        ),
+        ("let x = 1 << 64;", "Shift amount 64 is out of range 0..64"),
+        ("let x = 8 >> -1;", "Shift amount -1 is out of range 0..64"),
+        (
+            "let x = 1.5 & 1;",
+            "Bitwise operator `BitAnd` needs integers, got 1.5 and 1",
+        ),
+        (
+            "let x = 2 xor 1.0;",
+            "Bitwise operator `BitXor` needs integers, got 2 and 1",
+        ),
+    ];
+
+    for (code, message) in cases {
+        let error = MeadorCompiler::compile(code)?
+            .execute(&mut ExecutionContext::null())
+            .expect_err(code);
+
+        assert!(error.to_string().contains(message), "{}: {}", code, error);
+    }
+
+    let error = MeadorCompiler::compile("let x = true | false; let y = \"a\" << 1;")
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(error.contains("`BitOr`"), "{}", error);
+    assert!(error.contains("`ShiftLeft`"), "{}", error);
+
+    Ok(())
+}
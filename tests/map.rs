@@ -0,0 +1,218 @@
+use meadorc::{compiler::MeadorCompiler, runtime::ExecutionContext, value::Value};
+
+use anyhow::Result;
+
+fn map(entries: &[(&str, Value)]) -> Value {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect()
+}
+
+#[test]
+fn test_map_literal_and_field_access() -> Result<()> {
+    let code = r#"let point = { x: 1, "y": 2 + 3, x: 10 };
+                  let x = point.x;
+                  let y = point["y"];
+                  let nested = { inner: { value: [1, 2] } }.inner.value[1];
+                  let empty = len({});"#;
+
+    let program = MeadorCompiler::compile(code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"point".to_string()),
+        Some(&map(&[("x", Value::Integer(10)), ("y", Value::Integer(5))]))
+    );
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(10))
+    );
+    assert_eq!(
+        context.get_variable(&"y".to_string()),
+        Some(&Value::Integer(5))
+    );
+    assert_eq!(
+        context.get_variable(&"nested".to_string()),
+        Some(&Value::Integer(2))
+    );
+    assert_eq!(
+        context.get_variable(&"empty".to_string()),
+        Some(&Value::Integer(0))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_map_field_assignment() -> Result<()> {
+    let code = r#"let settings = { depth: 1 };
+                  let alias = settings;
+                  settings.depth = 2;
+                  settings["name"] = "deep";
+                  let grid = { rows: [{ cells: 0 }] };
+                  grid.rows[0].cells = alias.depth;"#;
+
+    let program = MeadorCompiler::compile(code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"alias".to_string()),
+        Some(&map(&[
+            ("depth", Value::Integer(2)),
+            ("name", Value::from("deep"))
+        ]))
+    );
+    assert_eq!(context.get_path("grid.rows"), {
+        let row = map(&[("cells", Value::Integer(2))]);
+        Some(Value::from(vec![row]))
+    });
+
+    Ok(())
+}
+
+#[test]
+fn test_map_config_from_host() -> Result<()> {
+    let code = "let total = config.rate * config.limits.max;
+                config.limits.max = 0;"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    let limits = map(&[("max", Value::Numerical(4.0))]);
+    let config = map(&[("rate", Value::Numerical(1.5)), ("limits", limits)]);
+    context.set_variable("config".to_string(), config);
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"total".to_string()),
+        Some(&Value::Numerical(6.0))
+    );
+    assert_eq!(
+        context.get_path("config.limits.max"),
+        Some(Value::Integer(0))
+    );
+    assert_eq!(context.get_path("config.missing"), None);
+    assert_eq!(context.get_path("config.rate.value"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_map_builtins_and_iteration() -> Result<()> {
+    let code = r#"let stock = { pears: 2, apples: 3 };
+                  let listed = keys(stock);
+                  let present = has(stock, "apples");
+                  let removed = remove(stock, "pears");
+                  let absent = has(stock, "pears");
+                  stock.plums = 4;
+                  let total = 0;
+                  let order = "";
+                  for fruit in stock {
+                      total = total + stock[fruit];
+                      order = order + fruit + " ";
+                  }
+                  let sum = 0;
+                  for value in [1, 2, 3] {
+                      sum = sum + value;
+                  }"#;
+
+    let program = MeadorCompiler::compile(code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    let expected = [
+        (
+            "listed",
+            Value::from(vec![Value::from("apples"), Value::from("pears")]),
+        ),
+        ("present", Value::Boolean(true)),
+        ("removed", Value::Integer(2)),
+        ("absent", Value::Boolean(false)),
+        ("total", Value::Integer(7)),
+        ("order", Value::from("apples plums ")),
+        ("sum", Value::Integer(6)),
+    ];
+
+    for (name, value) in expected {
+        assert_eq!(
+            context.get_variable(&name.to_string()),
+            Some(&value),
+            "{}",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_map_equality_and_printing() -> Result<()> {
+    let code = r#"let same = { a: 1, b: [2] } == { b: [2], a: 1 };
+                  let different = { a: 1 } != { a: "1" };
+                  print({ b: "x", "two words": 2, a: {} });"#;
+
+    let program = MeadorCompiler::compile(code)?;
+    let (mut context, output) = ExecutionContext::buffered();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"same".to_string()),
+        Some(&Value::Boolean(true))
+    );
+    assert_eq!(
+        context.get_variable(&"different".to_string()),
+        Some(&Value::Boolean(true))
+    );
+    assert_eq!(output.contents(), "{a: {}, b: \"x\", \"two words\": 2}\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_map_errors() -> Result<()> {
+    let cases = [
+        (
+            "let m = { a: 1 }; print(m.b);",
+            "Key \"b\" not found in map",
+        ),
+        (
+            "let m = {}; remove(m, \"a\");",
+            "Key \"a\" not found in map",
+        ),
+        (
+            "fn key(k) { return k; } let m = {}; print(m[key(1)]);",
+            "Map key must be a string, got number",
+        ),
+        (
+            "fn value(v) { return v; } for x in value(3) {}",
+            "Cannot iterate over number",
+        ),
+    ];
+
+    for (code, message) in cases {
+        let error = MeadorCompiler::compile(code)?
+            .execute(&mut ExecutionContext::null())
+            .expect_err(code);
+
+        assert!(error.to_string().contains(message), "{}: {}", code, error);
+    }
+
+    let error = MeadorCompiler::compile("let m = {}; let x = m[1]; for y in 2 {}")
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(error.contains("Map key must be a string, found number"));
+    assert!(error.contains("Cannot iterate over number"));
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+mod common;
+
+use meadorc::{
+    compiler::MeadorCompiler,
+    runtime::{DomainPolicy, ExecutionContext},
+    value::Value,
+};
+
+use anyhow::Result;
+use common::evaluate;
+
+#[test]
+fn test_math_functions() -> Result<()> {
+    let code = "let root = sqrt(16);
+                let power = exp(0);
+                let natural = ln(E);
+                let decimal = log10(1000);
+                let binary = log2(8);
+                let angle = atan2(1, 1) * 4;
+                let diagonal = hypot(3, 4);
+                let down = floor(-2.5);
+                let up = ceil(2.1);
+                let nearest = round(-2.5);
+                let truncated = trunc(-2.7);
+                let magnitude = abs(-1.5);
+                let negative = sign(-0.5);
+                let zero = sign(0.0);
+                let infinite = -INF;";
+
+    let context = evaluate(code)?;
+
+    let expected = [
+        ("root", 4.0),
+        ("power", 1.0),
+        ("natural", 1.0),
+        ("decimal", 3.0),
+        ("binary", 3.0),
+        ("angle", std::f64::consts::PI),
+        ("diagonal", 5.0),
+        ("down", -3.0),
+        ("up", 3.0),
+        ("nearest", -3.0),
+        ("truncated", -2.0),
+        ("magnitude", 1.5),
+        ("negative", -1.0),
+        ("zero", 0.0),
+        ("infinite", f64::NEG_INFINITY),
+    ];
+
+    for (name, value) in expected {
+        assert_eq!(
+            context.get_variable(&name.to_string()),
+            Some(&Value::Numerical(value)),
+            "{}",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_math_keeps_integers() -> Result<()> {
+    let code = "let magnitude = abs(-9007199254740993);
+                let negative = sign(-7);
+                let floored = floor(9007199254740993);
+                let smallest = min(3, 1.5, -2);
+                let largest = max(2, 2.5);
+                let single = max(7);
+                let low = clamp(-5, 0, 10);
+                let high = clamp(5.5, 0, 3);
+                let inside = clamp(2, 0.5, 3);";
+
+    let context = evaluate(code)?;
+
+    let expected = [
+        ("magnitude", Value::Integer(9007199254740993)),
+        ("negative", Value::Integer(-1)),
+        ("floored", Value::Integer(9007199254740993)),
+        ("smallest", Value::Integer(-2)),
+        ("largest", Value::Numerical(2.5)),
+        ("single", Value::Integer(7)),
+        ("low", Value::Integer(0)),
+        ("high", Value::Integer(3)),
+        ("inside", Value::Integer(2)),
+    ];
+
+    for (name, value) in expected {
+        assert_eq!(
+            context.get_variable(&name.to_string()),
+            Some(&value),
+            "{}",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_math_constants() -> Result<()> {
+    let code = "let circle = 2 * PI;
+                let bigger = INF > 9223372036854775807;
+                fn area(r) {
+                    return PI * r ^ 2;
+                }
+                let unit = area(1);
+                let E = 3;
+                let shadowed = E;";
+
+    let context = evaluate(code)?;
+
+    assert_eq!(
+        context.get_variable(&"circle".to_string()),
+        Some(&Value::Numerical(std::f64::consts::TAU))
+    );
+    assert_eq!(
+        context.get_variable(&"bigger".to_string()),
+        Some(&Value::Boolean(true))
+    );
+    assert_eq!(
+        context.get_variable(&"unit".to_string()),
+        Some(&Value::Numerical(std::f64::consts::PI))
+    );
+    assert_eq!(
+        context.get_variable(&"shadowed".to_string()),
+        Some(&Value::Integer(3))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_math_domain_policy() -> Result<()> {
+    let cases = [
+        ("let x = sqrt(-1);", "`sqrt` is undefined for -1"),
+        ("let x = ln(-2.5);", "`ln` is undefined for -2.5"),
+        ("let x = asin(2);", "`asin` is undefined for 2"),
+        ("let x = sin(INF);", "`sin` is undefined for inf"),
+        (
+            "let x = clamp(1, 3, 2);",
+            "`clamp` is undefined for 1, 3, 2",
+        ),
+    ];
+
+    for (code, message) in cases {
+        let program = MeadorCompiler::compile(code)?;
+
+        let mut context = ExecutionContext::null();
+        program.execute(&mut context)?;
+
+        let value = context.get_variable(&"x".to_string());
+        assert!(
+            matches!(value, Some(Value::Numerical(number)) if number.is_nan()),
+            "{}: {:?}",
+            code,
+            value
+        );
+
+        let mut context = ExecutionContext::null();
+        context.set_domain_policy(DomainPolicy::Error);
+
+        let error = program.execute(&mut context).expect_err(code);
+        assert!(
+            error
+                .to_string()
+                .contains(&format!("Math domain error: {}", message)),
+            "{}: {}",
+            code,
+            error
+        );
+    }
+
+    // Poles and NaN arguments are not domain errors.
+    let mut context = ExecutionContext::null();
+    context.set_domain_policy(DomainPolicy::Error);
+
+    MeadorCompiler::compile("let pole = ln(0); let nan = sqrt(INF - INF);")?
+        .execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"pole".to_string()),
+        Some(&Value::Numerical(f64::NEG_INFINITY))
+    );
+    assert!(matches!(
+        context.get_variable(&"nan".to_string()),
+        Some(Value::Numerical(number)) if number.is_nan()
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_math_errors() -> Result<()> {
+    let error = MeadorCompiler::compile("let x = min(); let y = max(1, \"a\"); let z = hypot(1);")
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(
+        error.contains("Function `min` expects at least 1 argument, got 0"),
+        "{}",
+        error
+    );
+    assert!(
+        error.contains("Argument 2 of `max` must be number, found string"),
+        "{}",
+        error
+    );
+    assert!(
+        error.contains("Function `hypot` expects 2 arguments, got 1"),
+        "{}",
+        error
+    );
+
+    let error = MeadorCompiler::compile("let min = -9223372036854775807 - 1; let x = abs(min);")?
+        .execute(&mut ExecutionContext::null())
+        .expect_err("abs should overflow");
+
+    assert!(
+        error
+            .to_string()
+            .contains("Integer overflow in `abs`: -9223372036854775808"),
+        "{}",
+        error
+    );
+
+    Ok(())
+}
@@ -0,0 +1,301 @@
+use meadorc::{compiler::MeadorCompiler, runtime::ExecutionContext, value::Value};
+
+use anyhow::Result;
+
+const OPERATORS: [&str; 20] = [
+    "+", "-", "*", "/", "//", "%", "^", "&", "|", "xor", "<<", ">>", "<", "<=", ">", ">=", "==",
+    "!=", "&&", "||",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Reference {
+    Number(f64),
+    Integer(i64),
+    Boolean(bool),
+}
+
+/// Why an expression has no value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Failure {
+    /// Rejected by the type checker.
+    Type,
+    /// Fails when it runs, such as a bitwise operator on a float.
+    Runtime,
+}
+
+fn precedence(operator: &str) -> u8 {
+    match operator {
+        "||" => 1,
+        "&&" => 2,
+        "<" | "<=" | ">" | ">=" | "==" | "!=" => 3,
+        "|" => 4,
+        "xor" => 5,
+        "&" => 6,
+        "<<" | ">>" => 7,
+        "+" | "-" => 8,
+        "*" | "/" | "//" | "%" => 9,
+        "^" => 10,
+        _ => unreachable!("unknown operator {operator}"),
+    }
+}
+
+fn is_right_associative(operator: &str) -> bool {
+    operator == "^"
+}
+
+/// Applies a single operator.
+fn apply(operator: &str, left: Reference, right: Reference) -> Result<Reference, Failure> {
+    use Reference::*;
+
+    match operator {
+        "==" => return Ok(Boolean(equal(left, right))),
+        "!=" => return Ok(Boolean(!equal(left, right))),
+        _ => {}
+    }
+
+    let result = match (left, right) {
+        (Integer(left), Integer(right)) => return apply_integers(operator, left, right),
+        (Number(_) | Integer(_), Number(_) | Integer(_)) => {
+            let (left, right) = (to_float(left), to_float(right));
+
+            match operator {
+                "+" => Number(left + right),
+                "-" => Number(left - right),
+                "*" => Number(left * right),
+                "/" => Number(left / right),
+                "//" => Number((left / right).floor()),
+                "%" => {
+                    // Floored: `rem_euclid` is never negative, so shift it for a negative divisor.
+                    let remainder = left.rem_euclid(right);
+
+                    Number(if right < 0.0 && remainder != 0.0 {
+                        remainder + right
+                    } else {
+                        remainder
+                    })
+                }
+                "^" => Number(left.powf(right)),
+                "<" => Boolean(left < right),
+                "<=" => Boolean(left <= right),
+                ">" => Boolean(left > right),
+                ">=" => Boolean(left >= right),
+                "&" | "|" | "xor" | "<<" | ">>" => return Err(Failure::Runtime),
+                _ => return Err(Failure::Type),
+            }
+        }
+        (Boolean(left), Boolean(right)) => match operator {
+            "&&" => Boolean(left && right),
+            "||" => Boolean(left || right),
+            _ => return Err(Failure::Type),
+        },
+        _ => return Err(Failure::Type),
+    };
+
+    Ok(result)
+}
+
+fn apply_integers(operator: &str, left: i64, right: i64) -> Result<Reference, Failure> {
+    use Reference::*;
+
+    let result = match operator {
+        "+" => Integer(left.checked_add(right).ok_or(Failure::Runtime)?),
+        "-" => Integer(left.checked_sub(right).ok_or(Failure::Runtime)?),
+        "*" => Integer(left.checked_mul(right).ok_or(Failure::Runtime)?),
+        "/" => Number(left as f64 / right as f64),
+        "//" | "%" if right == 0 => return Err(Failure::Runtime),
+        "//" => Integer((left as f64 / right as f64).floor() as i64),
+        "%" => Integer(left - right * (left as f64 / right as f64).floor() as i64),
+        "^" if right < 0 => Number((left as f64).powf(right as f64)),
+        "^" => Integer(left.checked_pow(right as u32).ok_or(Failure::Runtime)?),
+        "&" => Integer(left & right),
+        "|" => Integer(left | right),
+        "xor" => Integer(left ^ right),
+        "<<" | ">>" if !(0..64).contains(&right) => return Err(Failure::Runtime),
+        "<<" => Integer(left << right),
+        ">>" => Integer(left >> right),
+        "<" => Boolean(left < right),
+        "<=" => Boolean(left <= right),
+        ">" => Boolean(left > right),
+        ">=" => Boolean(left >= right),
+        _ => return Err(Failure::Type),
+    };
+
+    Ok(result)
+}
+
+/// `==` in scripts, which compares integers and floats by value.
+fn equal(left: Reference, right: Reference) -> bool {
+    match (left, right) {
+        (Reference::Boolean(left), Reference::Boolean(right)) => left == right,
+        (Reference::Boolean(_), _) | (_, Reference::Boolean(_)) => false,
+        (left, right) => to_float(left) == to_float(right),
+    }
+}
+
+fn to_float(reference: Reference) -> f64 {
+    match reference {
+        Reference::Number(number) => number,
+        Reference::Integer(integer) => integer as f64,
+        Reference::Boolean(_) => unreachable!("not a number"),
+    }
+}
+
+/// Evaluates `a op1 b op2 c` by grouping explicitly from the precedence table.
+fn reference_evaluate(
+    a: Reference,
+    first: &str,
+    b: Reference,
+    second: &str,
+    c: Reference,
+) -> Result<Reference, Failure> {
+    let group_left = precedence(first) > precedence(second)
+        || (precedence(first) == precedence(second) && !is_right_associative(first));
+
+    let inner = if group_left {
+        apply(first, a, b)
+    } else {
+        apply(second, b, c)
+    };
+
+    // The type checker still checks the outer operator when the inner one fails at
+    // runtime; every operator that can fail here gives a number.
+    let (inner, failed) = match inner {
+        Ok(inner) => (inner, false),
+        Err(Failure::Runtime) => (Reference::Integer(1), true),
+        Err(Failure::Type) => return Err(Failure::Type),
+    };
+
+    let outer = if group_left {
+        apply(second, inner, c)
+    } else {
+        apply(first, a, inner)
+    };
+
+    match outer {
+        Ok(_) if failed => Err(Failure::Runtime),
+        outer => outer,
+    }
+}
+
+fn to_value(reference: Reference) -> Value {
+    match reference {
+        Reference::Number(number) => Value::Numerical(number),
+        Reference::Integer(integer) => Value::Integer(integer),
+        Reference::Boolean(boolean) => Value::Boolean(boolean),
+    }
+}
+
+fn to_source(reference: Reference) -> String {
+    match reference {
+        Reference::Number(number) => format!("{:?}", number),
+        // Prefix `-` binds looser than `^`.
+        Reference::Integer(integer) if integer < 0 => format!("({})", integer),
+        Reference::Integer(integer) => integer.to_string(),
+        Reference::Boolean(boolean) => boolean.to_string(),
+    }
+}
+
+fn check_against_reference(operands: [Reference; 3]) -> Result<()> {
+    let [a, b, c] = operands;
+
+    for first in OPERATORS {
+        for second in OPERATORS {
+            let code = format!(
+                "let x = {} {} {} {} {};",
+                to_source(a),
+                first,
+                to_source(b),
+                second,
+                to_source(c)
+            );
+
+            let program = MeadorCompiler::compile(&code);
+
+            match reference_evaluate(a, first, b, second, c) {
+                Ok(expected) => {
+                    let mut context = ExecutionContext::null();
+                    program?.execute(&mut context)?;
+
+                    assert_eq!(
+                        context.get_variable(&"x".to_string()),
+                        Some(&to_value(expected)),
+                        "`{}`",
+                        code
+                    );
+                }
+                Err(Failure::Runtime) => {
+                    let mut context = ExecutionContext::null();
+                    let result = program?.execute(&mut context);
+
+                    assert!(result.is_err(), "`{}` should fail when it runs", code);
+                }
+                Err(Failure::Type) => assert!(program.is_err(), "`{}` should not type check", code),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_operator_pairs_numbers() -> Result<()> {
+    use Reference::Number;
+
+    check_against_reference([Number(7.0), Number(3.0), Number(2.0)])
+}
+
+#[test]
+fn test_operator_pairs_integers() -> Result<()> {
+    use Reference::Integer;
+
+    check_against_reference([Integer(7), Integer(3), Integer(2)])?;
+    check_against_reference([Integer(-6), Integer(4), Integer(3)])
+}
+
+#[test]
+fn test_operator_pairs_booleans() -> Result<()> {
+    use Reference::Boolean;
+
+    check_against_reference([Boolean(false), Boolean(true), Boolean(false)])?;
+    check_against_reference([Boolean(true), Boolean(false), Boolean(true)])
+}
+
+#[test]
+fn test_operator_chains() -> Result<()> {
+    let cases = [
+        ("1 - 2 * 3 + 4", Value::Integer(-1)),
+        ("8 / 2 ^ 2 * 3", Value::Numerical(6.0)),
+        ("2 ^ 3 ^ 2", Value::Integer(512)),
+        ("10 - 4 - 3", Value::Integer(3)),
+        ("64 / 4 / 2", Value::Numerical(8.0)),
+        ("1 + 2 * 3 ^ 2 - 4 / 2", Value::Numerical(17.0)),
+        ("2 * 3 < 4 + 5 && 1 == 1 || false", Value::Boolean(true)),
+        ("false && true || true", Value::Boolean(true)),
+        ("1 < 2 == true", Value::Boolean(true)),
+        ("1 + 2 << 1", Value::Integer(6)),
+        ("6 & 3 | 8", Value::Integer(10)),
+        ("5 xor 1 & 3", Value::Integer(4)),
+        ("1 | 2 == 3", Value::Boolean(true)),
+        ("7 // 2 * 2", Value::Integer(6)),
+        ("-7 // 2", Value::Integer(-4)),
+        ("1 << 2 ^ 3 >> 1", Value::Integer(128)),
+    ];
+
+    for (expression, expected) in cases {
+        let code = format!("let x = {};", expression);
+
+        let program = MeadorCompiler::compile(&code)?;
+        let mut context = ExecutionContext::null();
+
+        program.execute(&mut context)?;
+
+        assert_eq!(
+            context.get_variable(&"x".to_string()),
+            Some(&expected),
+            "`{}`",
+            expression
+        );
+    }
+
+    Ok(())
+}
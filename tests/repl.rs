@@ -0,0 +1,121 @@
+use meadorc::{
+    repl::{Input, Repl},
+    runtime::ExecutionContext,
+    value::Value,
+};
+
+use anyhow::Result;
+
+#[test]
+fn test_repl_persists_context() -> Result<()> {
+    let (context, output) = ExecutionContext::buffered();
+    let mut repl = Repl::new(context);
+
+    repl.feed("let x = 2;")?;
+    repl.feed("fn double(n) { return n * 2; }")?;
+    repl.feed("x = double(x);")?;
+
+    assert_eq!(
+        repl.context().get_variable(&"x".to_string()),
+        Some(&Value::Integer(4))
+    );
+    assert_eq!(output.contents(), "");
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_functions_replace_builtins() -> Result<()> {
+    let (context, output) = ExecutionContext::buffered();
+    let mut repl = Repl::new(context);
+
+    repl.feed("fn sin(a, b) { return a + b; }")?;
+    repl.feed("sin(1, 2)")?;
+
+    assert_eq!(output.contents(), "3\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_prints_bare_expressions() -> Result<()> {
+    let (context, output) = ExecutionContext::buffered();
+    let mut repl = Repl::new(context);
+
+    repl.feed("let name = \"meador\";")?;
+    repl.feed("1 + 2")?;
+    repl.feed("name + \"c\";")?;
+    repl.feed("print(\"void is not echoed\")")?;
+
+    assert_eq!(output.contents(), "3\n\"meadorc\"\nvoid is not echoed\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_multiline_input() -> Result<()> {
+    let (context, output) = ExecutionContext::buffered();
+    let mut repl = Repl::new(context);
+
+    assert_eq!(repl.feed("let i = 0;")?, Input::Complete);
+    assert_eq!(repl.feed("while i < 3 {")?, Input::Incomplete);
+    assert_eq!(
+        repl.feed("    if i > 0 { print(\"}\"); }")?,
+        Input::Incomplete
+    );
+    assert!(repl.is_pending());
+    assert_eq!(repl.feed("    i = i + 1;")?, Input::Incomplete);
+    assert_eq!(repl.feed("}")?, Input::Complete);
+    assert!(!repl.is_pending());
+
+    assert_eq!(output.contents(), "}\n}\n");
+    assert_eq!(
+        repl.context().get_variable(&"i".to_string()),
+        Some(&Value::Integer(3))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_survives_errors() -> Result<()> {
+    let (context, output) = ExecutionContext::buffered();
+    let mut repl = Repl::new(context);
+
+    repl.feed("let x = 1;")?;
+
+    assert!(repl.feed("let y = ;").is_err());
+    assert!(repl.feed("x + true").is_err());
+    assert!(repl.feed("missing").is_err());
+    assert!(!repl.is_pending());
+
+    repl.feed("{ let z = 1;")?;
+    repl.reset();
+    repl.feed("x")?;
+
+    assert_eq!(output.contents(), "1\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_comments() -> Result<()> {
+    let (context, output) = ExecutionContext::buffered();
+    let mut repl = Repl::new(context);
+
+    assert_eq!(repl.feed("// {")?, Input::Complete);
+    assert_eq!(repl.feed("let x = 1; /* {")?, Input::Incomplete);
+    assert_eq!(repl.feed("still a comment */ x = 2;")?, Input::Complete);
+    assert_eq!(repl.feed("x + 1 // }")?, Input::Complete);
+    // After a value `//` is floor division, so the braces are balanced.
+    assert_eq!(repl.feed("let m = {half: 7 // 2};")?, Input::Complete);
+    assert_eq!(repl.feed("m.half")?, Input::Complete);
+    // Elsewhere it starts a comment, even in the middle of an expression.
+    assert_eq!(repl.feed("fn f(n) { return n + // }")?, Input::Incomplete);
+    assert_eq!(repl.feed("n // 2; }")?, Input::Complete);
+    assert_eq!(repl.feed("f(2)")?, Input::Complete);
+
+    assert_eq!(output.contents(), "3\n3\n3\n");
+
+    Ok(())
+}
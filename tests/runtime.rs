@@ -0,0 +1,87 @@
+use meadorc::{
+    compiler::MeadorCompiler,
+    runtime::{ExecutionContext, OutputBuffer},
+};
+
+use anyhow::Result;
+
+struct FailingWriter;
+
+impl std::io::Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("sink closed"))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_buffered_output() -> Result<()> {
+    let code = r#"print("x", 1);
+                  print(true);
+                  print();"#
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let (mut context, output) = ExecutionContext::buffered();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(output.contents(), "x 1\ntrue\n\n");
+    assert_eq!(output.bytes(), b"x 1\ntrue\n\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_print_variants() -> Result<()> {
+    let code = r#"print("a", 1.5);
+                  println([1, "b"]);
+                  print();
+                  eprint("warning:", 2);
+                  eprintln("done");
+                  print("c", { key: "value" });"#;
+
+    let program = MeadorCompiler::compile(code)?;
+    let (mut context, output) = ExecutionContext::buffered();
+    let errors = OutputBuffer::default();
+    context.set_error_output(errors.clone());
+
+    program.execute(&mut context)?;
+    program.to_bytecode().execute(&mut context)?;
+
+    let expected = "a 1.5\n[1, \"b\"]\n\nc {key: \"value\"}\n";
+    assert_eq!(output.contents(), expected.repeat(2));
+    assert_eq!(errors.contents(), "warning: 2\ndone\n".repeat(2));
+
+    Ok(())
+}
+
+#[test]
+fn test_null_output() -> Result<()> {
+    let code = r#"print("discarded");"#.to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_output_failure() -> Result<()> {
+    let code = r#"print("lost");"#.to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::new(FailingWriter);
+
+    let result = program.execute(&mut context);
+
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("sink closed"));
+
+    Ok(())
+}
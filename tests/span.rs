@@ -0,0 +1,200 @@
+use meadorc::{
+    compiler::{CompilationError, MeadorCompiler},
+    runtime::ExecutionContext,
+    span::{LineCol, LineIndex, Span},
+};
+
+use anyhow::Result;
+
+#[test]
+fn test_line_index() {
+    let index = LineIndex::new("let x = 1;\r\nlet π = \"é\";\n\nprint(x);");
+
+    assert_eq!(index.line_col(0), LineCol { line: 1, column: 1 });
+    assert_eq!(index.line_col(8), LineCol { line: 1, column: 9 });
+    assert_eq!(index.line_col(12), LineCol { line: 2, column: 1 });
+    // `π` and `é` are two bytes each but one column.
+    assert_eq!(index.line_col(19), LineCol { line: 2, column: 7 });
+    assert_eq!(
+        index.line_col(22),
+        LineCol {
+            line: 2,
+            column: 10
+        }
+    );
+    assert_eq!(index.line_col(27), LineCol { line: 3, column: 1 });
+    assert_eq!(index.line_col(28), LineCol { line: 4, column: 1 });
+    assert_eq!(
+        index.line_col(1000),
+        LineCol {
+            line: 4,
+            column: 10
+        }
+    );
+
+    assert_eq!(index.line(1), "let x = 1;");
+    assert_eq!(index.line(2), "let π = \"é\";");
+    assert_eq!(index.line(3), "");
+    assert_eq!(index.line(4), "print(x);");
+    assert_eq!(index.line(5), "");
+}
+
+#[test]
+fn test_resolve_span() {
+    let index = LineIndex::new("let a = 1;\nlet b = a\n  + 2;");
+    let span = index.resolve(Span::new(19, 26));
+
+    assert_eq!(span.start, LineCol { line: 2, column: 9 });
+    assert_eq!(span.end, LineCol { line: 3, column: 6 });
+    assert_eq!(span.to_string(), "line 2, column 9");
+}
+
+#[test]
+fn test_compilation_error_location() {
+    let code = "let a = 1;\nlet b = a + \"text\";";
+
+    match MeadorCompiler::compile(code) {
+        Err(CompilationError::Type { pos, .. }) => {
+            assert_eq!(pos.span, Span::new(19, 29));
+            assert_eq!(pos.start, LineCol { line: 2, column: 9 });
+            assert_eq!(
+                pos.end,
+                LineCol {
+                    line: 2,
+                    column: 19
+                }
+            );
+        }
+        other => panic!("expected a type error, got {:?}", other.err()),
+    }
+
+    let error = MeadorCompiler::compile("let a = 1;\n\nlet b = ;")
+        .err()
+        .unwrap();
+
+    assert!(error
+        .to_string()
+        .starts_with("Syntax error at line 3, column 9:"));
+}
+
+#[test]
+fn test_runtime_error_location() -> Result<()> {
+    let code = "fn f(a) {\n    return a + missing;\n}\nlet x = f(1);";
+    let program = MeadorCompiler::compile(code)?;
+
+    let interpreted = program
+        .execute(&mut ExecutionContext::null())
+        .expect_err("interpreter should fail");
+    let compiled = program
+        .to_bytecode()
+        .execute(&mut ExecutionContext::null())
+        .expect_err("VM should fail");
+
+    for error in [interpreted, compiled] {
+        let span = error.span().expect("runtime error should be located");
+
+        assert_eq!(error.message(), "Unknown variable: missing");
+        assert_eq!(span.span, Span::new(25, 32));
+        assert_eq!(
+            span.start,
+            LineCol {
+                line: 2,
+                column: 16
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Runtime error at line 2, column 16: Unknown variable: missing\n    \
+             in `f`, called at line 4, column 9"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_statement_errors_are_located() -> Result<()> {
+    let code = "let x = 1;\nif x > 0 {\n    undeclared = x;\n}";
+    let program = MeadorCompiler::compile(code)?;
+
+    let interpreted = program.execute(&mut ExecutionContext::null()).unwrap_err();
+    let compiled = program
+        .to_bytecode()
+        .execute(&mut ExecutionContext::null())
+        .unwrap_err();
+
+    for error in [interpreted, compiled] {
+        let span = error.span().expect("runtime error should be located");
+
+        assert_eq!(span.start, LineCol { line: 3, column: 5 });
+        assert_eq!(
+            span.end,
+            LineCol {
+                line: 3,
+                column: 20
+            }
+        );
+        assert!(error.trace().is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_runtime_error_call_stack() -> Result<()> {
+    let code = r#"fn inner(n) {
+                      undeclared = n;
+                  }
+                  fn outer(n) {
+                      let m = n + 1;
+                      return inner(m);
+                  }
+                  print(outer(1));"#;
+    let program = MeadorCompiler::compile(code)?;
+
+    let interpreted = program.execute(&mut ExecutionContext::null()).unwrap_err();
+    let compiled = program
+        .to_bytecode()
+        .execute(&mut ExecutionContext::null())
+        .unwrap_err();
+
+    assert_eq!(interpreted.to_string(), compiled.to_string());
+
+    for error in [interpreted, compiled] {
+        assert_eq!(
+            error.span().unwrap().start,
+            LineCol {
+                line: 2,
+                column: 23
+            }
+        );
+
+        let trace: Vec<(&str, LineCol)> = error
+            .trace()
+            .iter()
+            .map(|frame| (frame.function.as_str(), frame.call_site.start))
+            .collect();
+
+        assert_eq!(
+            trace,
+            vec![
+                (
+                    "inner",
+                    LineCol {
+                        line: 6,
+                        column: 30
+                    }
+                ),
+                (
+                    "outer",
+                    LineCol {
+                        line: 8,
+                        column: 25
+                    }
+                ),
+            ]
+        );
+    }
+
+    Ok(())
+}
@@ -4,21 +4,22 @@ use anyhow::Result;
 
 #[test]
 fn test_conditional_statement() -> Result<()> {
-    let code = "if 5 > 2 {
-            let x = 3;
+    let code = "let x = 0;
+        if 5 > 2 {
+            x = 3;
         } else {
-            let x = 4;
+            x = 4;
         }"
     .to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context)?;
 
     assert_eq!(
         context.get_variable(&"x".to_string()),
-        Some(&Value::Numerical(3.0))
+        Some(&Value::Integer(3))
     );
 
     Ok(())
@@ -29,13 +30,13 @@ fn test_variable_assignment() -> Result<()> {
     let code = "let x = 2 + 2 * 2;".to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context)?;
 
     assert_eq!(
         context.get_variable(&"x".to_string()),
-        Some(&Value::Numerical(6.0))
+        Some(&Value::Integer(6))
     );
 
     Ok(())
@@ -45,18 +46,18 @@ fn test_variable_assignment() -> Result<()> {
 fn test_while_loop() -> Result<()> {
     let code = "let x = 0;
                 while x < 10 {
-                    let x = x + 1;
+                    x = x + 1;
                 }"
     .to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context)?;
 
     assert_eq!(
         context.get_variable(&"x".to_string()),
-        Some(&Value::Numerical(10.0))
+        Some(&Value::Integer(10))
     );
 
     Ok(())
@@ -67,19 +68,274 @@ fn test_code_block_statement() -> Result<()> {
     let code = "let x = 2;
                 {
                     let y = 3;
-                    let x = x + y;
+                    x = x + y;
+                }"
+    .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(5))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_block_shadowing() -> Result<()> {
+    let code = "let x = 1;
+                let inner = 0;
+                {
+                    let x = 2;
+                    inner = x;
+                }"
+    .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(1))
+    );
+    assert_eq!(
+        context.get_variable(&"inner".to_string()),
+        Some(&Value::Integer(2))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_conditional_body_scope() -> Result<()> {
+    let code = "let x = 1;
+                if true let x = 2;
+                if false {} else {
+                    let y = 3;
                 }"
     .to_string();
 
     let program = MeadorCompiler::compile(&code)?;
-    let mut context = ExecutionContext::new(std::io::stdout());
+    let mut context = ExecutionContext::null();
 
     program.execute(&mut context)?;
 
     assert_eq!(
         context.get_variable(&"x".to_string()),
-        Some(&Value::Numerical(5.0))
+        Some(&Value::Integer(1))
     );
+    assert_eq!(context.get_variable(&"y".to_string()), None);
 
     Ok(())
 }
+
+#[test]
+fn test_loop_body_variable_lifetime() -> Result<()> {
+    let code = "let i = 0;
+                let sum = 0;
+                while i < 3 {
+                    let step = i + 1;
+                    sum = sum + step;
+                    i = step;
+                }"
+    .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"sum".to_string()),
+        Some(&Value::Integer(6))
+    );
+    assert_eq!(context.get_variable(&"step".to_string()), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_assignment_updates_nearest_binding() -> Result<()> {
+    let code = "let x = 1;
+                {
+                    let x = 2;
+                    {
+                        x = 3;
+                    }
+                }
+                {
+                    x = 4;
+                }"
+    .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(4))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assignment_to_undeclared_variable() -> Result<()> {
+    let code = "{
+                    let x = 1;
+                }
+                x = 2;"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    let result = program.execute(&mut context);
+
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_identifiers_starting_with_keywords() -> Result<()> {
+    let code = "let lettuce = 1;
+                lettuce = 5;
+                let iffy = true;
+                let whilst = 0;
+                let elsewhere = 0;
+                let truthy = false;
+                if iffy {
+                    whilst = 2;
+                } else {
+                    elsewhere = 3;
+                }"
+    .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    for (name, value) in [
+        ("lettuce", Value::Integer(5)),
+        ("iffy", Value::Boolean(true)),
+        ("whilst", Value::Integer(2)),
+        ("elsewhere", Value::Integer(0)),
+        ("truthy", Value::Boolean(false)),
+    ] {
+        assert_eq!(context.get_variable(&name.to_string()), Some(&value));
+    }
+
+    for code in ["let let = 1;", "let x = 1; let else = 2;", "let true = 1;"] {
+        assert!(MeadorCompiler::compile(code).is_err(), "`{}`", code);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_for_loop() -> Result<()> {
+    let code = "let total = 0;
+        let count = 0;
+        for i in 0..10 {
+            if i == 2 {
+                continue;
+            }
+            if i > 5 {
+                break;
+            }
+            total = total + i;
+        }
+        for i in 3..1 count = count + 1;"
+        .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"total".to_string()),
+        Some(&Value::Integer(13))
+    );
+    assert_eq!(
+        context.get_variable(&"count".to_string()),
+        Some(&Value::Integer(0))
+    );
+    assert_eq!(context.get_variable(&"i".to_string()), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_for_loop_keyword_boundaries() -> Result<()> {
+    let code = "let forx = 0;
+                let inner = [1, 2, 3];
+                for index in inner {
+                    forx = forx + index;
+                }"
+    .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"forx".to_string()),
+        Some(&Value::Integer(6))
+    );
+
+    for code in ["forx in 0..2 {}", "for x in0..2 {}", "let in = 1;"] {
+        assert!(MeadorCompiler::compile(code).is_err(), "`{}`", code);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_break_from_while_loop() -> Result<()> {
+    let code = "let x = 0;
+        while true {
+            x = x + 1;
+            if x < 3 {
+                continue;
+            }
+            break;
+        }"
+    .to_string();
+
+    let program = MeadorCompiler::compile(&code)?;
+    let mut context = ExecutionContext::null();
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(3))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_loop_control_outside_of_loop() {
+    for code in [
+        "break;",
+        "if true { continue; }",
+        "while true { fn f() { break; } }",
+    ] {
+        let error = MeadorCompiler::compile(code).err().unwrap().to_string();
+
+        assert!(error.contains("outside of a loop"), "{}", code);
+    }
+}
@@ -0,0 +1,471 @@
+use meadorc::{
+    compiler::MeadorCompiler,
+    runtime::{ExecutionContext, MAX_CALL_DEPTH, MAX_VM_CALL_DEPTH},
+    value::Value,
+};
+
+use anyhow::Result;
+
+/// Runs `code` on both the tree-walking interpreter and the VM and checks that they
+/// print the same output and leave `variables` with the same values.
+fn run_on_both(code: &str, variables: &[&str]) -> Result<String> {
+    let program = MeadorCompiler::compile(code)?;
+
+    let (mut interpreted, interpreter_output) = ExecutionContext::buffered();
+    program.execute(&mut interpreted)?;
+
+    let (mut compiled, vm_output) = ExecutionContext::buffered();
+    program.to_bytecode().execute(&mut compiled)?;
+
+    assert_eq!(
+        interpreter_output.contents(),
+        vm_output.contents(),
+        "`{}`",
+        code
+    );
+
+    for variable in variables {
+        let variable = variable.to_string();
+
+        assert_eq!(
+            interpreted.get_variable(&variable),
+            compiled.get_variable(&variable),
+            "`{}` in `{}`",
+            variable,
+            code
+        );
+    }
+
+    Ok(vm_output.contents())
+}
+
+/// Checks that both engines fail `code` at runtime with the same message.
+fn fail_on_both(code: &str) -> Result<String> {
+    let program = MeadorCompiler::compile(code)?;
+
+    let interpreted = program
+        .execute(&mut ExecutionContext::null())
+        .expect_err("interpreter should fail");
+    let compiled = program
+        .to_bytecode()
+        .execute(&mut ExecutionContext::null())
+        .expect_err("VM should fail");
+
+    assert_eq!(interpreted.to_string(), compiled.to_string(), "`{}`", code);
+
+    Ok(compiled.to_string())
+}
+
+#[test]
+fn test_vm_expressions() -> Result<()> {
+    let code = r#"let a = 1 + 2 * 3 ^ 2 - 4 / 2;
+                  let b = 2 * 3 < 4 + 5 && 1 == 1 || false;
+                  let c = "meador" + "c";
+                  let d = sin(0) + cos(0);
+                  print(a, b, c, d);"#;
+
+    let output = run_on_both(code, &["a", "b", "c", "d"])?;
+
+    assert_eq!(output, "17 true meadorc 1\n");
+
+    let code = r#"let calls = 0;
+                  fn count() {
+                      calls = calls + 1;
+                      return true;
+                  }
+                  let a = false && count();
+                  let b = count() || count();
+                  let c = (true && count()) && (false || count());
+                  print(a, b, c, calls);"#;
+
+    let output = run_on_both(code, &["a", "b", "c", "calls"])?;
+
+    assert_eq!(output, "false true true 3\n");
+
+    let message = fail_on_both("fn f(v) { return v && true; } f(1);")?;
+    assert!(message.contains("Invalid types for binary operator `Conjuction`"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_unary_operators() -> Result<()> {
+    let code = r#"let x = 4;
+                  let a = -x ^ 2 - -1;
+                  let b = !(x > 3) || !!true;
+                  print(-(a + 1), b);"#;
+
+    let output = run_on_both(code, &["a", "b"])?;
+
+    assert_eq!(output, "14 true\n");
+
+    let message = fail_on_both("fn f(v) { return -v; } f(\"s\");")?;
+    assert!(message.contains("Invalid type for unary operator `Negation`"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_integers() -> Result<()> {
+    let code = r#"let big = 9007199254740993;
+                  let total = 0;
+                  for i in 0..4 {
+                      total = total + i * big % 10;
+                  }
+                  print(big + 2, total, 7 / 2, -7 % 3, 2 ^ -2);"#;
+
+    let output = run_on_both(code, &["big", "total"])?;
+
+    assert_eq!(output, "9007199254740995 18 3.5 2 0.25\n");
+
+    let message = fail_on_both("fn twice(x) { return x * 2; } twice(9223372036854775807);")?;
+    assert!(message.contains("Integer overflow in `Multiply`: 9223372036854775807 and 2"));
+    assert!(message.contains("in `twice`"));
+
+    let code = r#"let hash = 5381;
+                  for c in [104, 105] {
+                      hash = ((hash << 5) + hash) xor c & 65535;
+                  }
+                  print(hash, -9 // 4, -9 % 4, -1 >> 60, 6 | 1);"#;
+
+    let output = run_on_both(code, &["hash"])?;
+
+    assert_eq!(output, "5861124 -3 3 -1 7\n");
+
+    let message = fail_on_both("fn shift(x, n) { return x << n; } shift(1, 64);")?;
+    assert!(message.contains("Shift amount 64 is out of range 0..64"));
+    assert!(message.contains("in `shift`"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_math() -> Result<()> {
+    let code = r#"fn area(r) {
+                      return PI * r ^ 2;
+                  }
+                  let unit = area(1);
+                  let floored = map([-5, 2.5, 9], floor);
+                  let bounds = [min(3, -2, 1.5), max(INF, 0), clamp(7, 0, 5)];
+                  print(round(unit * 100), floored, bounds, sqrt(-1));"#;
+
+    let output = run_on_both(code, &["unit", "floored", "bounds"])?;
+
+    assert_eq!(output, "314 [-5, 2, 9] [-2, inf, 5] NaN\n");
+
+    let message = fail_on_both("fn f(x) { return abs(x); } f(-9223372036854775807 - 1);")?;
+    assert!(message.contains("Integer overflow in `abs`"));
+    assert!(message.contains("in `f`"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_scoping() -> Result<()> {
+    let code = r#"let x = 1;
+                  let y = 0;
+                  {
+                      let x = 10;
+                      {
+                          let x = x + 5;
+                          y = x;
+                      }
+                      x = x + 1;
+                      print(x);
+                  }
+                  let x = x + 100;
+                  print(x, y);"#;
+
+    let output = run_on_both(code, &["x", "y"])?;
+
+    assert_eq!(output, "11\n101 15\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_loops_and_conditionals() -> Result<()> {
+    let code = r#"let i = 0;
+                  let small = 0;
+                  while i < 10 {
+                      let next = i + 1;
+                      if i < 5 {
+                          small = small + 1;
+                      } else {
+                          print(i);
+                      }
+                      i = next;
+                  }"#;
+
+    let output = run_on_both(code, &["i", "small"])?;
+
+    assert_eq!(output, "5\n6\n7\n8\n9\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_for_loops() -> Result<()> {
+    let code = r#"let total = 0;
+                  for i in 0..5 {
+                      for j in i..5 {
+                          if j == 3 {
+                              continue;
+                          }
+                          if i + j > 6 {
+                              break;
+                          }
+                          total = total + j;
+                      }
+                  }
+                  fn first_square_above(limit) {
+                      for n in 1..limit {
+                          if n * n > limit {
+                              return n;
+                          }
+                      }
+                      return -1;
+                  }
+                  let square = first_square_above(20);
+                  print(total, square);"#;
+
+    let output = run_on_both(code, &["total", "square"])?;
+
+    assert_eq!(output, "20 5\n");
+
+    let message = fail_on_both("fn f(n) { for i in 0..n {} } f(\"ten\");")?;
+    assert!(message.contains("Range bounds must be numbers"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_float_ranges() -> Result<()> {
+    // Past 2^53 adding 1 to a float rounds back down, yet the range still ends.
+    let code = "let halves = 0;
+                for x in 0.5..2 {
+                    halves = halves + x;
+                }
+                let s = 2.0 ^ 53;
+                let large = 0;
+                for i in s..s + 2 {
+                    large = large + 1;
+                }
+                let empty = 0;
+                for i in 0..INF - INF {
+                    empty = empty + 1;
+                }
+                println(halves, large, empty);";
+
+    let output = run_on_both(code, &["halves", "large", "empty"])?;
+
+    assert_eq!(output, "2 2 0\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_arrays() -> Result<()> {
+    let code = r#"let values = [1, 2, 3];
+                  let alias = values;
+                  values[0] = 10;
+                  push(alias, 4);
+                  fn twice(x) {
+                      return x * 2;
+                  }
+                  let doubled = map(values, twice);
+                  let grid = [[1, 2], [3, 4]];
+                  grid[1][1] = pop(doubled);
+                  print(values, doubled, grid, sum(slice(values, 1, 3)));"#;
+
+    let output = run_on_both(code, &["values", "alias", "doubled", "grid"])?;
+
+    assert_eq!(output, "[10, 2, 3, 4] [20, 4, 6] [[1, 2], [3, 8]] 5\n");
+
+    let message = fail_on_both("fn at(a, i) { return a[i]; } print(at([1, 2], 2));")?;
+    assert!(message.contains("Index 2 out of bounds for array of length 2"));
+    assert!(message.contains("in `at`"));
+
+    let message = fail_on_both("fn f(x) { return x[0]; } print(map([1], f));")?;
+    assert!(message.contains("Cannot index into number"));
+    assert!(message.contains("in `f`"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_maps() -> Result<()> {
+    let code = r#"let stock = { pears: 2, "green apples": 3 };
+                  let alias = stock;
+                  stock.pears = stock.pears + 1;
+                  alias["plums"] = 4;
+                  let names = "";
+                  for name in stock {
+                      if name == "plums" {
+                          continue;
+                      }
+                      names = names + name + ";";
+                  }
+                  let total = 0;
+                  for count in [stock.pears, remove(stock, "plums")] {
+                      total = total + count;
+                  }
+                  print(stock, names, total, has(alias, "plums"), keys({ b: 1, a: 2 }));"#;
+
+    let output = run_on_both(code, &["stock", "alias", "names", "total"])?;
+
+    assert_eq!(
+        output,
+        "{\"green apples\": 3, pears: 3} green apples;pears; 7 false [\"a\", \"b\"]\n"
+    );
+
+    let message = fail_on_both("fn get(m, k) { return m[k]; } print(get({ a: 1 }, \"b\"));")?;
+    assert!(message.contains("Key \"b\" not found in map"));
+    assert!(message.contains("in `get`"));
+
+    let message = fail_on_both("fn each(c) { for x in c {} } each(true);")?;
+    assert!(message.contains("Cannot iterate over boolean"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_recursion() -> Result<()> {
+    let code = r#"fn fib(n) {
+                      if n < 2 {
+                          return n;
+                      }
+                      return fib(n - 1) + fib(n - 2);
+                  }
+                  fn factorial(n) {
+                      let result = 1;
+                      while n > 1 {
+                          result = result * n;
+                          n = n - 1;
+                      }
+                      return result;
+                  }
+                  let x = fib(15);
+                  let y = factorial(10);"#;
+
+    run_on_both(code, &["x", "y"])?;
+
+    let program = MeadorCompiler::compile(code)?;
+    let mut context = ExecutionContext::null();
+    program.to_bytecode().execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"x".to_string()),
+        Some(&Value::Integer(610))
+    );
+    assert_eq!(
+        context.get_variable(&"y".to_string()),
+        Some(&Value::Integer(3628800))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_functions_see_globals() -> Result<()> {
+    let code = r#"let counter = 0;
+                  fn bump(step) {
+                      counter = counter + step;
+                  }
+                  bump(2);
+                  bump(3);
+                  print(counter);"#;
+
+    let output = run_on_both(code, &["counter"])?;
+
+    assert_eq!(output, "5\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_embedder_state() -> Result<()> {
+    let code = r#"let total = rate * 2;
+                  rate = lookup(total);"#;
+
+    let program = MeadorCompiler::compile(code)?.to_bytecode();
+    let mut context = ExecutionContext::null();
+
+    context.set_variable("rate".to_string(), Value::Numerical(1.5));
+    context.register_function("lookup", 1, |arguments| {
+        let amount = f64::try_from(&arguments[0])?;
+        Ok((amount + 1.0).into())
+    });
+
+    program.execute(&mut context)?;
+
+    assert_eq!(
+        context.get_variable(&"total".to_string()),
+        Some(&Value::Numerical(3.0))
+    );
+    assert_eq!(
+        context.get_variable(&"rate".to_string()),
+        Some(&Value::Numerical(4.0))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_runtime_errors() -> Result<()> {
+    let message = fail_on_both("fn f() { return missing; } f();")?;
+    assert!(message.contains("Unknown variable: missing"));
+
+    let message = fail_on_both("fn f() { undeclared = 1; } f();")?;
+    assert!(message.contains("Assignment to undeclared variable: undeclared"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_call_depth() -> Result<()> {
+    let code = "fn f(n) { if n == 0 { return 0; } return f(n - 1); } let x = f(100);";
+    run_on_both(code, &["x"])?;
+
+    let program = MeadorCompiler::compile("fn f() { f(); }\nf();")?;
+
+    let interpreted = program
+        .execute(&mut ExecutionContext::null())
+        .expect_err("interpreter should fail");
+    let compiled = program
+        .to_bytecode()
+        .execute(&mut ExecutionContext::null())
+        .expect_err("VM should fail");
+
+    for (error, limit) in [(interpreted, MAX_CALL_DEPTH), (compiled, MAX_VM_CALL_DEPTH)] {
+        // Recursive calls from the same site are written once.
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Runtime error at line 1, column 10: Maximum call depth of {} exceeded\n    \
+                 in `f`, called at line 1, column 10 ({} times)\n    \
+                 in `f`, called at line 2, column 1",
+                limit,
+                limit - 1
+            )
+        );
+        assert_eq!(error.trace().len(), limit);
+    }
+
+    for engine in 0..2 {
+        let mut context = ExecutionContext::null();
+        context.set_max_call_depth(20);
+
+        let error = match engine {
+            0 => program.execute(&mut context),
+            _ => program.to_bytecode().execute(&mut context),
+        }
+        .expect_err("call depth is limited");
+
+        assert!(error
+            .to_string()
+            .contains("Maximum call depth of 20 exceeded"));
+    }
+
+    Ok(())
+}